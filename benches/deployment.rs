@@ -0,0 +1,58 @@
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use deploy::analyze::DependencyAnalyzer;
+
+const PACKAGES: usize = 5_000;
+const IMPORTS_PER_PACKAGE: usize = 5;
+
+/// Writes an acyclic project where every package imports a handful of
+/// lower-numbered packages plus the standard library
+fn synthetic_project() -> tempfile::TempDir {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+
+    for i in 0..PACKAGES {
+        let mut source = format!("package p{}\nimport (\n\"fmt\"\n", i);
+        for k in 1..=IMPORTS_PER_PACKAGE.min(i) {
+            source.push_str(&format!("\"p{}\"\n", (i * 31 + k * 17) % i));
+        }
+        source.push(')');
+        std::fs::write(root.path().join(format!("p{}.go", i)), source).unwrap();
+    }
+
+    root
+}
+
+fn bench_deployment(c: &mut Criterion) {
+    let root = synthetic_project();
+    let mut analyzer = DependencyAnalyzer::new();
+    analyzer.analyze_directory(root.path()).unwrap();
+
+    let mut group = c.benchmark_group("5k packages");
+    group.sample_size(20);
+    group.bench_function("generate_deployment_order", |b| {
+        b.iter(|| black_box(analyzer.generate_deployment_order().len()))
+    });
+    group.bench_function("deployment_waves", |b| {
+        b.iter(|| black_box(analyzer.deployment_waves().len()))
+    });
+    group.finish();
+}
+
+fn bench_analyze_directory(c: &mut Criterion) {
+    let root = synthetic_project();
+
+    let mut group = c.benchmark_group("5k files");
+    group.sample_size(10);
+    group.bench_function("analyze_directory", |b| {
+        b.iter(|| {
+            let mut analyzer = DependencyAnalyzer::new();
+            analyzer.analyze_directory(root.path()).unwrap();
+            black_box(analyzer.packages().count())
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_deployment, bench_analyze_directory);
+criterion_main!(benches);
@@ -1,42 +1,710 @@
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::Path;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
 
+use cache::ParseCache;
+use constraint::BuildConstraint;
+use progress::ProgressTracker;
+use rayon::prelude::*;
 use tree_sitter::{Parser, Query, QueryCursor, StreamingIteratorMut};
 use tree_sitter_go;
 
+mod arch;
+mod cache;
+mod churn;
+mod codependency;
+mod constraint;
+mod csv;
+mod d3;
+mod dgml;
+mod diff;
+mod dot;
+mod explain;
+mod fan;
+#[cfg(test)]
+mod fixture;
+mod flamegraph;
+mod gate;
+mod generate;
+mod grammar;
+mod graph;
+mod hierarchy;
+mod html;
+mod lint;
+mod manifest;
+mod mermaid;
+mod nix;
+mod plan;
+mod priority;
+mod progress;
+mod schema;
+mod score;
+mod simulate;
+mod tag;
+mod visibility;
+
+pub use arch::{ArchRules, ArchViolation};
+pub use cache::CACHE_DIR;
+pub use codependency::CoDependency;
+pub use diff::{AnalysisDiff, ImportEdge, ScoreChange};
+pub use fan::FanReport;
+pub use gate::{Baseline, TransitiveViolation};
+pub use grammar::grammar_info;
+pub use lint::{LintKind, LintOptions, LintWarning};
+pub use plan::{DeploymentPlan, PlanViolation};
+pub use priority::Priorities;
+pub use progress::Progress;
+pub use schema::report_schema;
+pub use score::ScoreFormula;
+pub use simulate::RemovalImpact;
+pub use tag::TagRule;
+pub use visibility::InternalViolation;
+
+/// Hash map used for the analyzer's internal state.
+///
+/// FxHash is much cheaper than the default SipHash for the short string keys
+/// hashed over and over while scoring; none of the keys are attacker-chosen
+/// in a way that would make hash flooding a concern. Iteration order is
+/// arbitrary either way, so results are sorted wherever order matters.
+type Map<K, V> = rustc_hash::FxHashMap<K, V>;
+/// Hash set counterpart of [`Map`]
+type Set<T> = rustc_hash::FxHashSet<T>;
+
 /// Represents a Go package with its dependencies and coupling metrics.
 ///
 /// The coupling score (instability) is calculated as:
 /// I = Ce/(Ca+Ce) where:
 ///  - Ca = Afferent coupling (incoming dependencies)
 ///  - Ce = Efferent coupling (outgoing dependencies)
-#[derive(Debug, PartialEq)]
-pub(self) struct Package {
+#[derive(Debug, Default, PartialEq)]
+pub struct Package {
     /// Name of the package
     name: String,
+    /// Key of the package in the analyzer: its import path inside a gno
+    /// module, where package clause names collide across directories, and
+    /// its name everywhere else
+    key: String,
+    /// Directory of the package's first analyzed file as a `/`-separated
+    /// path, relative to the project root when it was analyzed through
+    /// `analyze_directory`
+    import_path: String,
     /// Set of packages that this package imports
-    imports: HashSet<String>,
+    imports: Set<String>,
+    /// Every import spec as written, file by file in source order
+    import_specs: Vec<ImportSpec>,
     /// Instability score (0.0 to 1.0, higher means more unstable)
     coupling_score: f64,
+    /// Whether the package neither imports anything nor is imported, which
+    /// leaves its instability undefined; `coupling_score` is 0.0 then
+    isolated: bool,
+    /// Number of source lines in the package
+    lines_of_code: usize,
+    /// Number of exported top-level functions
+    exported_functions: usize,
+    /// Source files declaring this package
+    files: Vec<PathBuf>,
+    /// Syntax tree size of each source file, see `ast_nodes`
+    file_ast_nodes: Map<PathBuf, usize>,
+    /// Qualified references (`name.X`) of each source file, by qualifier,
+    /// see `weighted_efferent`
+    file_qualifiers: Map<PathBuf, BTreeMap<String, usize>>,
+    /// Import specs of each source file
+    file_import_specs: Map<PathBuf, Vec<ImportSpec>>,
+    /// Number of lines of each source file, summed into `lines_of_code`
+    file_lines: Map<PathBuf, usize>,
+    /// Exported functions of each source file, summed into
+    /// `exported_functions`
+    file_exported_functions: Map<PathBuf, usize>,
+    /// Top-level type declarations of each source file, see `abstractness`
+    file_types: Map<PathBuf, TypeCounts>,
+    /// Source files marked with a `// Code generated ... DO NOT EDIT.` header
+    generated_files: Set<PathBuf>,
+}
+
+impl Package {
+    /// Name of the package
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Directory-based import path of the package, e.g. `cmd/server` for a
+    /// `main` package in `<root>/cmd/server`
+    pub fn import_path(&self) -> &str {
+        &self.import_path
+    }
+
+    /// Import paths of the package as written, in no particular order
+    pub fn imports(&self) -> impl Iterator<Item = &str> {
+        self.imports.iter().map(String::as_str)
+    }
+
+    /// Maps each import alias to the path it names, e.g. `f` to `fmt` for
+    /// `import f "fmt"`.
+    ///
+    /// Blank (`_`) and dot (`.`) imports bind no name and are left out. If
+    /// files of the package bind one alias to different paths, the file
+    /// analyzed last wins.
+    pub fn aliases(&self) -> BTreeMap<&str, &str> {
+        self.import_specs
+            .iter()
+            .filter_map(|spec| Some((spec.alias.as_deref()?, spec.path.as_str())))
+            .filter(|(alias, _)| *alias != "_" && *alias != ".")
+            .collect()
+    }
+
+    /// Recomputes the package-wide imports and totals from the
+    /// contributions of its files
+    fn merge_files(&mut self) {
+        self.import_specs = self
+            .files
+            .iter()
+            .filter_map(|file| self.file_import_specs.get(file))
+            .flatten()
+            .cloned()
+            .collect();
+        self.imports = self
+            .import_specs
+            .iter()
+            .map(|spec| normalize_import_path(&spec.path))
+            .collect();
+        self.lines_of_code = self.file_lines.values().sum();
+        self.exported_functions = self.file_exported_functions.values().sum();
+    }
+
+    /// Whether every spec importing `import` is a blank import, so the
+    /// package only depends on its initialization side effects
+    fn is_blank_import(&self, import: &str) -> bool {
+        let mut specs = self
+            .import_specs
+            .iter()
+            .filter(|spec| normalize_import_path(&spec.path) == import)
+            .peekable();
+        specs.peek().is_some() && specs.all(|spec| spec.kind == ImportKind::Blank)
+    }
+
+    /// Instability computed by the last `calculate_coupling_scores`, 0.0
+    /// before that
+    pub fn coupling_score(&self) -> f64 {
+        self.coupling_score
+    }
+
+    /// Total number of syntax tree nodes over all of the package's files, a
+    /// rough size and complexity proxy
+    fn ast_nodes(&self) -> usize {
+        self.file_ast_nodes.values().sum()
+    }
+
+    /// Number of qualified references (`fmt.Println`, `avl.Tree`) to
+    /// imported packages over all of the package's files, a measure of how
+    /// heavily the package uses its imports rather than how many it has.
+    ///
+    /// A reference is attributed by the name its file binds the import to:
+    /// the alias, or the last path element without a `/vN` major version
+    /// suffix. Blank and dot imports bind no name and never count.
+    fn weighted_efferent(&self) -> usize {
+        self.files
+            .iter()
+            .map(|file| {
+                let Some(qualifiers) = self.file_qualifiers.get(file) else {
+                    return 0;
+                };
+                let bound: Set<&str> = self
+                    .file_import_specs
+                    .get(file)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(ImportSpec::bound_name)
+                    .collect();
+                bound
+                    .into_iter()
+                    .filter_map(|name| qualifiers.get(name))
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// Share of the package's top-level types that are interfaces, 0.0 for
+    /// a package declaring no types
+    fn abstractness(&self) -> f64 {
+        let (types, interfaces) = self
+            .file_types
+            .values()
+            .fold((0, 0), |(types, interfaces), counts| {
+                (types + counts.types, interfaces + counts.interfaces)
+            });
+        if types == 0 {
+            0.0
+        } else {
+            interfaces as f64 / types as f64
+        }
+    }
+
+    /// Whether every source file of the package is generated code
+    fn is_generated(&self) -> bool {
+        !self.files.is_empty()
+            && self
+                .files
+                .iter()
+                .all(|file| self.generated_files.contains(file))
+    }
+}
+
+/// Information extracted from a single source file
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+struct SourceInfo {
+    /// Declared package name, empty if there is no package clause
+    package: String,
+    /// Import specs in source order
+    imports: Vec<ImportSpec>,
+    /// Number of exported top-level functions
+    exported_functions: usize,
+    /// Number of nodes in the syntax tree, named or not
+    ast_nodes: usize,
+    /// Qualified references (`name.X`), by qualifier
+    qualifiers: BTreeMap<String, usize>,
+    /// Expression of the `//go:build` line, if the file has a valid one
+    build_constraint: Option<BuildConstraint>,
+    /// Whether the file carries a generated-code header
+    generated: bool,
+    /// Top-level type declarations
+    types: TypeCounts,
+    /// `ERROR` and `MISSING` nodes, with an empty path
+    syntax_errors: Vec<SyntaxError>,
+}
+
+/// A syntax error tree-sitter recovered from while parsing a source file
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SyntaxError {
+    /// Path of the source file
+    pub path: PathBuf,
+    /// 1-based line of the error
+    pub line: usize,
+    /// 1-based column of the error, in bytes
+    pub column: usize,
+    /// What went wrong, e.g. ``unexpected `)` `` or `missing identifier`
+    pub message: String,
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.path.display(),
+            self.line,
+            self.column,
+            self.message
+        )
+    }
+}
+
+/// Number of top-level type declarations in a source file
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+struct TypeCounts {
+    /// Every declared type, aliases excluded
+    types: usize,
+    /// Declared interface types
+    interfaces: usize,
+}
+
+/// How an import was written
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportForm {
+    /// A standalone `import "x"` declaration
+    Single,
+    /// A spec inside an `import ( ... )` block
+    Grouped,
+}
+
+/// What an import makes available to the importing file
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportKind {
+    /// The package's exported names, under its own name or an alias
+    #[default]
+    Normal,
+    /// Nothing; `import _ "x"` only runs the package's initialization
+    Blank,
+    /// The package's exported names, merged into the file's namespace by
+    /// `import . "x"`
+    Dot,
+}
+
+impl ImportKind {
+    /// Kind of an import bound to `alias`
+    fn from_alias(alias: Option<&str>) -> Self {
+        match alias {
+            Some("_") => ImportKind::Blank,
+            Some(".") => ImportKind::Dot,
+            _ => ImportKind::Normal,
+        }
+    }
+
+    fn is_normal(&self) -> bool {
+        *self == ImportKind::Normal
+    }
+}
+
+/// A single import spec from a source file
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct ImportSpec {
+    /// Imported path
+    path: String,
+    /// Name the import is bound to (`j "encoding/json"`), including `_`
+    /// and `.`; `None` when the package's own name is used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alias: Option<String>,
+    /// Whether the import came from a single or a grouped declaration
+    form: ImportForm,
+    /// Blank or dot import, omitted for normal imports
+    #[serde(default, skip_serializing_if = "ImportKind::is_normal")]
+    kind: ImportKind,
+}
+
+impl ImportSpec {
+    /// Name the import is referred to by in its file, `None` for blank and
+    /// dot imports
+    fn bound_name(&self) -> Option<&str> {
+        match self.alias.as_deref() {
+            Some("_" | ".") => None,
+            Some(alias) => Some(alias),
+            None => last_path_element(&self.path),
+        }
+    }
+}
+
+/// Last element of an import path, skipping a `/vN` major version suffix
+fn last_path_element(path: &str) -> Option<&str> {
+    let mut elements = path.rsplit('/');
+    let last = elements.next().filter(|last| !last.is_empty())?;
+    let is_major_version =
+        last.len() > 1 && last.starts_with('v') && last[1..].bytes().all(|b| b.is_ascii_digit());
+    match elements.next() {
+        Some(previous) if is_major_version => Some(previous),
+        _ => Some(last),
+    }
 }
 
 /// Analysis result for a single package
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, schemars::JsonSchema)]
 struct PackageAnalysis {
     name: String,
+    /// Key of the package in the analyzer, see `Package::key`
+    #[serde(skip)]
+    #[schemars(skip)]
+    key: String,
+    /// Directory-based import path, see `Package::import_path`
+    import_path: String,
     coupling_score: f64,
+    /// No imports and no dependents, so `coupling_score` carries no meaning
+    isolated: bool,
     imports: Vec<String>,
+    /// Tags from the rules matching the package's directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// Import specs as written, only included in detailed output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    import_specs: Option<Vec<ImportSpec>>,
+    /// Imports of packages made up solely of generated files
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    generated_imports: Vec<String>,
     metrics: DetailedMetrics,
+    /// Custom score from `--score-formula`, if one was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<f64>,
 }
 
 /// Detailed dependency metrics
-#[derive(serde::Serialize, Default)]
+#[derive(serde::Serialize, schemars::JsonSchema, Default)]
 struct DetailedMetrics {
-    afferent_coupling: usize, // incoming dependencies
-    efferent_coupling: usize, // outgoing dependencies
-    instability: f64,         // instability score
-    abstractness: f64,        // TODO
-    distance: f64,            // TODO: distance from main sequence
+    afferent_coupling: usize,  // incoming dependencies
+    efferent_coupling: usize,  // outgoing dependencies
+    instability: f64,          // instability score
+    abstractness: f64,         // interfaces / all declared types
+    distance: f64,             // distance from main sequence, |A + I - 1|
+    lines_of_code: usize,      // source lines
+    exported_functions: usize, // public API surface
+    ast_nodes: usize,          // syntax tree size
+    weighted_efferent: usize,  // qualified references to imports
+    /// Imports by direction in the directory tree, only in detailed output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    edge_directions: Option<hierarchy::EdgeDirections>,
+    /// Commits touching the package, only once git churn was loaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    churn: Option<usize>,
+    /// Churn times afferent coupling, only once git churn was loaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bus_factor_risk: Option<f64>,
+}
+
+/// Predicate deciding whether a package stays in the report
+type ReportFilter<'a> = Box<dyn Fn(&PackageAnalysis) -> bool + 'a>;
+
+/// Raw coupling counts for a single package
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct CouplingCounts {
+    afferent: usize, // incoming dependencies
+    efferent: usize, // outgoing dependencies
+    external: usize, // outgoing dependencies on packages outside the project
+}
+
+impl CouplingCounts {
+    /// Instability Ce/(Ca+Ce), with external imports weighted by
+    /// `external_weight` (1.0 if unset); 0.0 for isolated packages
+    fn instability(self, external_weight: Option<f64>) -> f64 {
+        let afferent = self.afferent as f64;
+        let internal = (self.efferent - self.external) as f64;
+        let efferent = internal + self.external as f64 * external_weight.unwrap_or(1.0);
+        if afferent + efferent > 0.0 {
+            efferent / (afferent + efferent)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Export formats that render the whole dependency graph rather than the
+/// filtered package list
+const GRAPH_FORMATS: [&str; 9] = [
+    "opml",
+    "communities",
+    "hierarchy",
+    "dgml",
+    "nix",
+    "flamegraph",
+    "d3",
+    "dot",
+    "mermaid",
+];
+
+/// Options controlling how analysis results are exported
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Show detailed metrics for each package
+    pub detailed: bool,
+    /// Omit packages that import only external packages and that no
+    /// analyzed package depends on
+    pub hide_leaf_utilities: bool,
+    /// Order in which packages are reported
+    pub sort_by: SortKey,
+    /// Rank packages by this formula instead, overriding `sort_by`
+    pub score_formula: Option<ScoreFormula>,
+    /// Only report packages with at least this instability, see
+    /// `packages_in_range`; isolated packages have none and are left out
+    pub min_score: Option<f64>,
+    /// Glob patterns of package names to leave out of the report
+    pub exclude_packages: Vec<String>,
+    /// Only report packages in this instability zone
+    pub zone: Option<Zone>,
+    /// Only report packages carrying at least one of these tags
+    pub tags: Vec<String>,
+    /// Report at most this many packages, after filtering and sorting
+    pub limit: Option<usize>,
+    /// Append a legend explaining node labels and edge styles to `dot`
+    /// and `mermaid` output
+    pub legend: bool,
+    /// How the report was produced; when set, JSON output becomes an
+    /// object with `meta`, `summary` and `packages` fields instead of a
+    /// bare list
+    pub meta: Option<ReportMeta>,
+}
+
+impl ExportOptions {
+    /// Names of the set options that narrow down the reported packages
+    fn package_filters(&self) -> Vec<&'static str> {
+        let mut filters = Vec::new();
+        if self.hide_leaf_utilities {
+            filters.push("hide_leaf_utilities");
+        }
+        if !self.exclude_packages.is_empty() {
+            filters.push("exclude_packages");
+        }
+        if self.min_score.is_some() {
+            filters.push("min_score");
+        }
+        if self.zone.is_some() {
+            filters.push("zone");
+        }
+        if !self.tags.is_empty() {
+            filters.push("tags");
+        }
+        if self.limit.is_some() {
+            filters.push("limit");
+        }
+        filters
+    }
+}
+
+/// Project-wide figures reported along with the package list
+#[derive(Debug, Clone, PartialEq, serde::Serialize, schemars::JsonSchema)]
+pub struct ReportSummary {
+    /// Newman modularity of the whole import graph, see
+    /// `DependencyAnalyzer::modularity`; report filters don't change it
+    pub modularity: f64,
+}
+
+/// Provenance of a report, so a saved report can be understood later
+#[derive(Debug, Clone, PartialEq, serde::Serialize, schemars::JsonSchema)]
+pub struct ReportMeta {
+    /// Version of this crate
+    pub version: String,
+    /// Analyzed project paths
+    pub paths: Vec<PathBuf>,
+    /// Options that affect the results, keyed by option name
+    pub options: serde_json::Value,
+}
+
+impl ReportMeta {
+    /// Creates report metadata for the current crate version
+    pub fn new(paths: Vec<PathBuf>, options: serde_json::Value) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            paths,
+            options,
+        }
+    }
+}
+
+/// Metric packages are ranked by in reports, highest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    /// Instability score
+    #[default]
+    Instability,
+    /// Number of exported top-level functions
+    ApiSurface,
+    /// Git churn times afferent coupling, see `load_git_churn`
+    BusRisk,
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "instability" => Ok(SortKey::Instability),
+            "api-surface" => Ok(SortKey::ApiSurface),
+            "bus-risk" => Ok(SortKey::BusRisk),
+            _ => Err(format!(
+                "unknown sort key `{}` (expected instability, api-surface or bus-risk)",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortKey::Instability => write!(f, "instability"),
+            SortKey::ApiSurface => write!(f, "api-surface"),
+            SortKey::BusRisk => write!(f, "bus-risk"),
+        }
+    }
+}
+
+/// Band of instability a package falls in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    /// Instability below 1/3
+    Stable,
+    /// Instability from 1/3 to 2/3
+    Balanced,
+    /// Instability above 2/3
+    Unstable,
+    /// No coupling at all, so no instability
+    Isolated,
+}
+
+impl Zone {
+    /// Zone of a package with the given instability
+    fn of(isolated: bool, instability: f64) -> Self {
+        if isolated {
+            Zone::Isolated
+        } else if instability < 1.0 / 3.0 {
+            Zone::Stable
+        } else if instability > 2.0 / 3.0 {
+            Zone::Unstable
+        } else {
+            Zone::Balanced
+        }
+    }
+
+    /// Capitalized name, used as a category by the graph exports
+    fn label(self) -> &'static str {
+        match self {
+            Zone::Stable => "Stable",
+            Zone::Balanced => "Balanced",
+            Zone::Unstable => "Unstable",
+            Zone::Isolated => "Isolated",
+        }
+    }
+}
+
+impl std::str::FromStr for Zone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stable" => Ok(Zone::Stable),
+            "balanced" => Ok(Zone::Balanced),
+            "unstable" => Ok(Zone::Unstable),
+            "isolated" => Ok(Zone::Isolated),
+            _ => Err(format!(
+                "unknown zone `{}` (expected stable, balanced, unstable or isolated)",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Zone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label().to_lowercase())
+    }
+}
+
+/// A file that was skipped because it couldn't be read or wasn't valid UTF-8
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedFile {
+    /// Path of the skipped file
+    pub path: PathBuf,
+    /// Kind of the I/O error that caused it to be skipped
+    pub kind: std::io::ErrorKind,
+}
+
+impl std::fmt::Display for SkippedFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.kind == std::io::ErrorKind::InvalidData {
+            write!(f, "{}: not valid UTF-8", self.path.display())
+        } else {
+            write!(f, "{}: {}", self.path.display(), self.kind)
+        }
+    }
+}
+
+/// Target of a `replace` directive in `gno.mod`
+#[derive(Debug, Clone, PartialEq)]
+enum Replacement {
+    /// Local directory containing the replacement package
+    Directory(PathBuf),
+    /// Another import path
+    ImportPath(String),
 }
 
 /// Analyzes dependencies between Go packages and calculates coupling metrics.
@@ -46,8 +714,55 @@ struct DetailedMetrics {
 /// unstable packages.
 #[derive(Default, Debug)]
 pub struct DependencyAnalyzer {
-    /// Map of package names to their corresponding Package instances
-    packages: HashMap<String, Package>,
+    /// Packages by key, see `Package::key`
+    packages: Map<String, Package>,
+    /// Coupling counts computed by `calculate_coupling_scores`.
+    /// Cleared whenever `packages` changes so stale counts are never read.
+    coupling_cache: Option<Map<String, CouplingCounts>>,
+    /// Directory of each analyzed source file, mapped to its package key
+    package_dirs: Map<PathBuf, String>,
+    /// Package directories by trailing segments of their import path, so
+    /// that `demo/avl` and `avl` both list `.../p/demo/avl`, each flagged
+    /// with whether the segments are its whole import path; see
+    /// `resolve_import`
+    dir_suffixes: Map<String, BTreeMap<PathBuf, bool>>,
+    /// Module paths declared by `gno.mod` files, by the directory holding them
+    modules: Map<PathBuf, String>,
+    /// Import path replacements declared by `replace` directives in
+    /// `gno.mod`, sorted so that a reverse scan meets longer paths first
+    replacements: BTreeMap<String, Replacement>,
+    /// Files that couldn't be read or decoded as UTF-8
+    skipped: Vec<SkippedFile>,
+    /// Leave imports of generated-only packages out of the coupling counts
+    discount_generated_imports: bool,
+    /// Preferences among packages that are ready to deploy at the same time
+    priorities: Priorities,
+    /// Weight of an external import in the efferent coupling, 1.0 if unset
+    external_weight: Option<f64>,
+    /// Leave imports between packages of the same cycle out of the coupling counts
+    ignore_cycle_edges: bool,
+    /// Leave blank imports out of the efferent coupling
+    exclude_blank_imports: bool,
+    /// Rules deriving package tags from package directories
+    tag_rules: Vec<TagRule>,
+    /// Syntax errors of every analyzed file, in analysis order
+    syntax_errors: Vec<SyntaxError>,
+    /// Commits touching each package, `None` until `load_git_churn`
+    churn: Option<Map<String, usize>>,
+    /// Project directories passed to `analyze_directory`, which package
+    /// import paths are relative to
+    roots: Vec<PathBuf>,
+    /// Paths, relative to the analyzed root, that `analyze_directory` skips
+    exclude: Vec<glob::Pattern>,
+    /// Reuse and update the parse cache of each analyzed root
+    parse_cache: bool,
+    /// Source files `analyze_directory` took from the parse cache
+    cache_hits: usize,
+    /// Package each analyzed source file belongs to
+    file_packages: Map<PathBuf, String>,
+    /// Tags files' `//go:build` constraints are checked against; `None`
+    /// includes every file
+    build_tags: Option<Set<String>>,
 }
 
 impl DependencyAnalyzer {
@@ -56,6 +771,119 @@ impl DependencyAnalyzer {
         Self::default()
     }
 
+    /// Sets glob patterns of paths for `analyze_directory` to skip, such as
+    /// `*_test.go` or `vendor`.
+    ///
+    /// Patterns match paths relative to the analyzed root, and `*` also
+    /// matches `/`. A matching directory is skipped with everything below
+    /// it.
+    ///
+    /// # Returns
+    ///
+    /// * `Err` if a pattern isn't a valid glob
+    pub fn set_exclude_patterns(&mut self, patterns: &[String]) -> Result<(), AnalyzeError> {
+        self.exclude = patterns
+            .iter()
+            .map(|pattern| {
+                glob::Pattern::new(pattern)
+                    .map_err(|e| AnalyzeError::InvalidPattern(format!("{}: {}", pattern, e)))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    /// Only analyzes files whose `//go:build` constraint holds when exactly
+    /// `tags` are set, like `go build -tags`; with `None`, the default,
+    /// constraints are ignored and every file is analyzed.
+    ///
+    /// Files without a constraint, or with one that can't be parsed, are
+    /// always analyzed. File name suffixes such as `_linux.go` aren't
+    /// treated as constraints. Takes effect for files analyzed afterwards.
+    pub fn set_build_tags(&mut self, tags: Option<&[String]>) {
+        self.build_tags = tags.map(|tags| tags.iter().cloned().collect());
+    }
+
+    /// Makes `analyze_directory` keep what it extracts from each source
+    /// file in `CACHE_DIR` below the analyzed root, and skip parsing files
+    /// whose content hasn't changed since. Off by default.
+    pub fn set_parse_cache(&mut self, enabled: bool) {
+        self.parse_cache = enabled;
+    }
+
+    /// Returns how many source files `analyze_directory` took from the
+    /// parse cache instead of parsing them
+    pub fn cache_hits(&self) -> usize {
+        self.cache_hits
+    }
+
+    /// Sets the weights `generate_deployment_order` uses to choose among
+    /// packages whose dependencies are all deployed
+    pub fn set_priorities(&mut self, priorities: Priorities) {
+        self.priorities = priorities;
+    }
+
+    /// Sets whether imports of packages made up solely of generated files
+    /// (protobuf stubs, mocks, ...) are left out of the coupling counts.
+    ///
+    /// Such imports are still listed as generated imports in the report.
+    pub fn set_discount_generated_imports(&mut self, discount: bool) {
+        if self.discount_generated_imports != discount {
+            self.discount_generated_imports = discount;
+            self.coupling_cache = None;
+        }
+    }
+
+    /// Sets how much an import of a package outside the project counts
+    /// toward the efferent coupling used by `calculate_coupling_scores`.
+    ///
+    /// 1.0, the default, counts external imports like internal ones and 0.0
+    /// ignores them; values in between reflect that external dependencies
+    /// are real but less under the project's control.
+    ///
+    /// # Returns
+    ///
+    /// * `Err` if `weight` is not between 0.0 and 1.0
+    pub fn set_external_weight(&mut self, weight: f64) -> Result<(), AnalyzeError> {
+        if !(0.0..=1.0).contains(&weight) {
+            return Err(AnalyzeError::InvalidRange(format!(
+                "external weight ({}) must be between 0 and 1",
+                weight
+            )));
+        }
+        if self.external_weight != Some(weight) {
+            self.external_weight = Some(weight);
+            self.coupling_cache = None;
+        }
+        Ok(())
+    }
+
+    /// Sets whether imports between packages of the same import cycle are
+    /// left out of the coupling counts.
+    ///
+    /// Each cycle is then treated as one internally cohesive unit, so
+    /// instability only reflects the acyclic structure around it. See
+    /// `ignored_cycle_edges` for how many imports this leaves out.
+    pub fn set_ignore_cycle_edges(&mut self, ignore: bool) {
+        if self.ignore_cycle_edges != ignore {
+            self.ignore_cycle_edges = ignore;
+            self.coupling_cache = None;
+        }
+    }
+
+    /// Sets whether blank imports (`import _ "x"`) are left out of the
+    /// efferent coupling of the importing package.
+    ///
+    /// A blank import only runs the imported package's initialization, so
+    /// the importer doesn't depend on its API. The imported package still
+    /// counts it as a dependent. Paths also imported normally elsewhere in
+    /// the package keep counting.
+    pub fn set_exclude_blank_imports(&mut self, exclude: bool) {
+        if self.exclude_blank_imports != exclude {
+            self.exclude_blank_imports = exclude;
+            self.coupling_cache = None;
+        }
+    }
+
     /// Analyzes a single Go source file and extracts its package dependencies.
     ///
     /// Uses tree-sitter to parse the Go source file and extract:
@@ -68,146 +896,957 @@ impl DependencyAnalyzer {
     ///
     /// # Returns
     ///
-    /// * `Ok(())` if analysis succeeds
+    /// * `Ok(())` if analysis succeeds, or if the file isn't valid UTF-8, in
+    ///   which case it is recorded in `skipped_files` instead
     /// * `Err` with a description if any error occurs during analysis
-    pub fn analyze_file(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        let source_code = std::fs::read_to_string(path)?;
-        let (package_name, imports) = self.extract_package_and_imports(&source_code)?;
-
-        if !package_name.is_empty() {
-            self.packages.insert(
-                package_name.clone(),
-                Package {
-                    name: package_name,
-                    imports,
-                    coupling_score: 0.0,
-                },
-            );
-        }
+    pub fn analyze_file(&mut self, path: &Path) -> Result<(), AnalyzeError> {
+        let source_code = match read_source(path) {
+            Ok(source_code) => source_code,
+            Err(err) if err.kind() == std::io::ErrorKind::InvalidData => {
+                self.skipped.push(SkippedFile {
+                    path: path.to_path_buf(),
+                    kind: err.kind(),
+                });
+                return Ok(());
+            }
+            Err(err) => return Err(err.into()),
+        };
+        self.analyze_source(path, &source_code, source_code.lines().count())
+    }
 
-        Ok(())
+    /// Analyzes Go source read from `reader`, e.g. stdin, as if it were the
+    /// file `name`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Source of the Go code, read to the end
+    /// * `name` - Path the source is recorded under; its directory decides
+    ///   which package the source belongs to, as for `analyze_file`
+    ///
+    /// # Returns
+    ///
+    /// * `Err` if reading fails or the source isn't valid UTF-8
+    pub fn analyze_reader(
+        &mut self,
+        mut reader: impl std::io::Read,
+        name: &str,
+    ) -> Result<(), AnalyzeError> {
+        let mut source_code = String::new();
+        reader.read_to_string(&mut source_code)?;
+        self.analyze_source(Path::new(name), &source_code, source_code.lines().count())
     }
 
-    /// Extracts package name and imports from Go source code
-    fn extract_package_and_imports(
-        &self,
-        source_code: &str,
-    ) -> Result<(String, HashSet<String>), AnalysisError> {
-        let mut parser = Parser::new();
-        let language = tree_sitter_go::LANGUAGE;
-        parser.set_language(&language.into())?;
+    /// Analyzes only the package clause and imports of a Go source file.
+    ///
+    /// Meant for huge files: the file is read line by line and only the
+    /// header up to the first top-level declaration is kept in memory. The
+    /// remaining lines are counted but never stored, so `lines_of_code` is
+    /// still accurate while exported functions aren't counted.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the Go source file
+    pub fn analyze_file_imports(&mut self, path: &Path) -> Result<(), AnalyzeError> {
+        let file = std::fs::File::open(path)?;
+        let (header, lines_of_code) = read_import_section(std::io::BufReader::new(file))?;
+        self.analyze_source(path, &header, lines_of_code)
+    }
 
-        let tree = parser
-            .parse(source_code, None)
-            .ok_or_else(|| AnalysisError::ParseError("Failed to parse source code".to_string()))?;
+    /// Analyzes all `.go` and `gno.mod` files below a directory.
+    ///
+    /// Source files are read and parsed in parallel on the current rayon
+    /// thread pool, then recorded one by one in path order, so the result
+    /// doesn't depend on thread scheduling. Run this inside
+    /// `ThreadPool::install` to control the number of threads.
+    ///
+    /// Files that can't be read (permission denied, removed mid-walk, not
+    /// valid UTF-8, ...) don't abort the walk; they are recorded and
+    /// available through `skipped_files`.
+    ///
+    /// Besides the patterns from `set_exclude_patterns`, paths matching a
+    /// pattern listed in a `.deployignore` file at the root are skipped,
+    /// see `read_deployignore`.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Project directory to walk
+    pub fn analyze_directory(&mut self, root: &Path) -> Result<(), AnalyzeError> {
+        self.analyze_directory_with_progress(root, |_| {})
+    }
 
-        // Query for package clause and imports
-        let query = Query::new(
-            &language.into(),
-            r#"
-            (package_clause
-              (package_identifier) @package)
-            
-            ; single import
-            (import_declaration
-              (import_spec 
-                (interpreted_string_literal) @import))
-            
-            ; group import
-            (import_declaration
-              (import_spec_list
-                (import_spec
-                  (interpreted_string_literal) @import)))
-            "#,
-        )?;
+    /// Like `analyze_directory`, calling `on_progress` after every parsed
+    /// source file.
+    ///
+    /// Files are parsed in parallel, so `on_progress` may be called from
+    /// several threads, though never concurrently.
+    pub fn analyze_directory_with_progress(
+        &mut self,
+        root: &Path,
+        on_progress: impl Fn(Progress) + Send,
+    ) -> Result<(), AnalyzeError> {
+        let normalized_root = normalize_dir(root);
+        if !self.roots.contains(&normalized_root) {
+            self.roots.push(normalized_root);
+        }
 
-        let mut cursor = QueryCursor::new();
-        let mut matches = cursor.matches(&query, tree.root_node(), source_code.as_bytes());
+        let mut exclude = self.exclude.clone();
+        exclude.extend(read_deployignore(root)?);
+        let is_included = |entry: &walkdir::DirEntry| {
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            !exclude.iter().any(|pattern| pattern.matches_path(relative))
+        };
 
-        let mut current_package = String::new();
-        let mut imports = HashSet::new();
+        let mut sources = Vec::new();
+        for entry in walkdir::WalkDir::new(root)
+            .into_iter()
+            .filter_entry(is_included)
+        {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    if let Some(path) = err.path() {
+                        let kind = err
+                            .io_error()
+                            .map_or(std::io::ErrorKind::Other, |e| e.kind());
+                        self.skipped.push(SkippedFile {
+                            path: path.to_path_buf(),
+                            kind,
+                        });
+                    }
+                    continue;
+                }
+            };
 
-        while let Some(matched) = matches.next_mut() {
-            for capture in matched.captures {
-                let capture_text = capture
-                    .node
-                    .utf8_text(source_code.as_bytes())?
-                    .trim_matches('"');
+            let path = entry.path();
+            if entry.file_name() == "gno.mod" {
+                match std::fs::read_to_string(path) {
+                    Ok(contents) => self.apply_gno_mod(path, &contents),
+                    Err(err) => self.skipped.push(SkippedFile {
+                        path: path.to_path_buf(),
+                        kind: err.kind(),
+                    }),
+                }
+            } else if path.extension().is_some_and(|ext| ext == "go") {
+                sources.push(entry.into_path());
+            }
+        }
+        sources.sort();
 
-                match query.capture_names()[capture.index as usize] {
-                    "package" => {
-                        current_package = capture_text.to_string();
+        // Unreadable files are skipped, parse failures abort the analysis
+        let cache = self.parse_cache.then(|| ParseCache::load(root));
+        let relative = |path: &Path| path.strip_prefix(root).unwrap_or(path).to_path_buf();
+        let tracker = ProgressTracker::new(sources.len());
+        let on_progress = Mutex::new(on_progress);
+        let parsed: Vec<_> = sources
+            .into_par_iter()
+            .map(|path| {
+                let result = read_source(&path).map(|contents| {
+                    let hash = cache::content_hash(&contents);
+                    if let Some((info, lines)) = cache
+                        .as_ref()
+                        .and_then(|cache| cache.get(&relative(&path), hash))
+                    {
+                        return Ok((info, lines, hash, true));
+                    }
+                    self.extract_source_info(&contents)
+                        .map(|info| (info, contents.lines().count(), hash, false))
+                });
+                let lines = match &result {
+                    Ok(Ok((_, lines, _, _))) => *lines,
+                    _ => 0,
+                };
+                let on_progress = on_progress.lock().unwrap_or_else(|err| err.into_inner());
+                on_progress(tracker.tick(lines));
+                (path, result)
+            })
+            .collect();
+
+        let mut updated = cache.map(|_| ParseCache::default());
+        for (path, result) in parsed {
+            match result {
+                Ok(parsed) => {
+                    let (info, lines_of_code, hash, cached) = parsed?;
+                    if cached {
+                        self.cache_hits += 1;
                     }
-                    "import" => {
-                        imports.insert(capture_text.to_string());
+                    if let Some(updated) = &mut updated {
+                        updated.insert(relative(&path), hash, info.clone(), lines_of_code);
                     }
-                    _ => {}
+                    self.record_source(&path, info, lines_of_code);
                 }
+                Err(err) => self.skipped.push(SkippedFile {
+                    path,
+                    kind: err.kind(),
+                }),
             }
         }
 
-        Ok((current_package, imports))
+        // The cache only saves time, so failing to write it is no error
+        if let Some(updated) = updated {
+            let _ = updated.save(root);
+        }
+
+        Ok(())
     }
 
-    /// Calculates coupling scores for all analyzed packages.
+    /// Iterates over the analyzed packages in no particular order.
     ///
-    /// For each package, computes:
-    ///  1. Afferent coupling (Ca) - number of packages that depend on it
-    ///  2. Efferent coupling (Ce) - number of packages it depends on
-    ///  3. Instability (I) = Ce/(Ca+Ce)
+    /// ```
+    /// use deploy::analyze::{AnalyzeError, DependencyAnalyzer};
     ///
-    /// A higher score (closer to 1.0) indicates that the package is more unstable
-    /// and dependent on other packages.
-    pub fn calculate_coupling_scores(&mut self) {
-        // Calculate afferent coupling (incoming dependencies)
-        let package_afferent_coupling = self.calculate_afferent_coupling();
+    /// let mut analyzer = DependencyAnalyzer::new();
+    /// # let dir = tempfile::tempdir().unwrap();
+    /// # std::fs::create_dir(dir.path().join("app")).unwrap();
+    /// # std::fs::write(dir.path().join("app/app.go"), "package app\nimport \"fmt\"").unwrap();
+    /// analyzer.analyze_directory(dir.path())?;
+    /// analyzer.calculate_coupling_scores();
+    ///
+    /// for package in analyzer.packages() {
+    ///     let imports: Vec<&str> = package.imports().collect();
+    ///     println!("{} {:.2} {:?}", package.name(), package.coupling_score(), imports);
+    /// }
+    /// # assert_eq!(analyzer.packages().count(), 1);
+    /// # Ok::<(), AnalyzeError>(())
+    /// ```
+    pub fn packages(&self) -> impl Iterator<Item = &Package> {
+        self.packages.values()
+    }
 
-        // Update coupling scores for each package
-        for package in self.packages.values_mut() {
-            let afferent = *package_afferent_coupling.get(&package.name).unwrap_or(&0.0);
-            let efferent = package.imports.len() as f64;
-
-            if (afferent + efferent) > 0.0 {
-                package.coupling_score = efferent / (afferent + efferent);
-                println!(
-                    "{}: {:.2} - {} imports",
-                    package.name,
-                    package.coupling_score,
-                    package.imports.len()
-                );
-            }
-        }
+    /// Returns the syntax errors tree-sitter recovered from in analyzed
+    /// files, which can explain imports that weren't picked up
+    pub fn syntax_errors(&self) -> &[SyntaxError] {
+        &self.syntax_errors
     }
 
-    /// Calculate afferent coupling for all packages
-    fn calculate_afferent_coupling(&self) -> HashMap<String, f64> {
-        self.packages
-            .keys()
-            .map(|name| {
-                let afferent = self
-                    .packages
-                    .values()
-                    .filter(|p| p.imports.contains(name))
-                    .count() as f64;
-                (name.clone(), afferent)
+    /// Returns the files skipped because they couldn't be read or weren't valid UTF-8
+    pub fn skipped_files(&self) -> &[SkippedFile] {
+        &self.skipped
+    }
+
+    /// Records the package declared by a source file read from `path`
+    ///
+    /// Nothing derived from `source_code` beyond the extracted metadata is
+    /// kept, so sources can be dropped as soon as this returns.
+    fn analyze_source(
+        &mut self,
+        path: &Path,
+        source_code: &str,
+        lines_of_code: usize,
+    ) -> Result<(), AnalyzeError> {
+        let info = self.extract_source_info(source_code)?;
+        self.record_source(path, info, lines_of_code);
+        Ok(())
+    }
+
+    /// Records the package metadata extracted from the source file at `path`
+    ///
+    /// A package split across several files imports everything any of its
+    /// files imports; recording a file again replaces its previous imports.
+    fn record_source(&mut self, path: &Path, info: SourceInfo, lines_of_code: usize) {
+        if let (Some(tags), Some(constraint)) = (&self.build_tags, &info.build_constraint)
+            && !constraint.is_satisfied(tags)
+        {
+            self.remove_file(path);
+            return;
+        }
+
+        let SourceInfo {
+            package: package_name,
+            imports: file_specs,
+            exported_functions,
+            ast_nodes,
+            qualifiers,
+            build_constraint: _,
+            generated,
+            types,
+            syntax_errors,
+        } = info;
+
+        // A package clause the parser couldn't recover is replaced by the
+        // last element of the directory's import path, the name the package
+        // is most likely imported under
+        let package_name = if package_name.is_empty() && !syntax_errors.is_empty() {
+            let dir = normalize_dir(path.parent().unwrap_or(Path::new("")));
+            last_path_element(&self.dir_import_path(&dir))
+                .unwrap_or_default()
+                .to_string()
+        } else {
+            package_name
+        };
+
+        self.syntax_errors.retain(|error| error.path != path);
+        self.syntax_errors
+            .extend(syntax_errors.into_iter().map(|error| SyntaxError {
+                path: path.to_path_buf(),
+                ..error
+            }));
+
+        if package_name.is_empty() {
+            self.remove_file(path);
+        } else {
+            self.coupling_cache = None;
+            let dir = normalize_dir(path.parent().unwrap_or(Path::new("")));
+            // Within a gno module, package clause names collide across
+            // directories, so the module import path identifies the package
+            let module_path = self.module_import_path(&dir);
+            let key = module_path.clone().unwrap_or_else(|| package_name.clone());
+            // A file whose package clause changed leaves its old package
+            if self
+                .file_packages
+                .get(path)
+                .is_some_and(|previous| *previous != key)
+            {
+                self.remove_file(path);
+            }
+            let dir_path = module_path.unwrap_or_else(|| self.dir_import_path(&dir));
+            let segments: Vec<&str> = dir_path.split('/').filter(|s| !s.is_empty()).collect();
+            for start in 0..segments.len() {
+                self.dir_suffixes
+                    .entry(segments[start..].join("/"))
+                    .or_default()
+                    .insert(dir.clone(), start == 0);
+            }
+            self.package_dirs.insert(dir, key.clone());
+
+            let import_path = match self.packages.get(&key) {
+                Some(previous) => previous.import_path.clone(),
+                None => dir_path,
+            };
+            let package = self.packages.entry(key.clone()).or_insert_with(|| Package {
+                key: key.clone(),
+                import_path,
+                ..Default::default()
+            });
+            package.name = package_name;
+            if !package.files.iter().any(|file| file == path) {
+                package.files.push(path.to_path_buf());
+            }
+            package.file_ast_nodes.insert(path.to_path_buf(), ast_nodes);
+            package
+                .file_qualifiers
+                .insert(path.to_path_buf(), qualifiers);
+            package.file_types.insert(path.to_path_buf(), types);
+            package
+                .file_import_specs
+                .insert(path.to_path_buf(), file_specs);
+            package.file_lines.insert(path.to_path_buf(), lines_of_code);
+            package
+                .file_exported_functions
+                .insert(path.to_path_buf(), exported_functions);
+            if generated {
+                package.generated_files.insert(path.to_path_buf());
+            } else {
+                package.generated_files.remove(path);
+            }
+            package.coupling_score = 0.0;
+            package.isolated = false;
+            package.merge_files();
+            self.file_packages.insert(path.to_path_buf(), key);
+        }
+    }
+
+    /// Forgets everything a source file contributed, as if it had never
+    /// been analyzed, for re-analysis after the file was deleted.
+    ///
+    /// The file's package is updated from its remaining files, or removed
+    /// along with its directory mappings when no file is left. To pick up
+    /// changes to a file, `analyze_file` it again instead; that replaces
+    /// its contribution.
+    ///
+    /// # Returns
+    ///
+    /// * Whether `path` had been analyzed as part of a package
+    pub fn remove_file(&mut self, path: &Path) -> bool {
+        self.syntax_errors.retain(|error| error.path != path);
+        let Some(name) = self.file_packages.remove(path) else {
+            return false;
+        };
+        self.coupling_cache = None;
+
+        let Some(package) = self.packages.get_mut(&name) else {
+            return true;
+        };
+        package.files.retain(|file| file != path);
+        package.file_ast_nodes.remove(path);
+        package.file_qualifiers.remove(path);
+        package.file_types.remove(path);
+        package.file_import_specs.remove(path);
+        package.file_lines.remove(path);
+        package.file_exported_functions.remove(path);
+        package.generated_files.remove(path);
+
+        if package.files.is_empty() {
+            self.packages.remove(&name);
+            self.package_dirs.retain(|_, package| *package != name);
+            self.prune_dir_suffixes();
+        } else {
+            package.merge_files();
+        }
+        true
+    }
+
+    /// Drops directories no package is declared in anymore from
+    /// `dir_suffixes`
+    fn prune_dir_suffixes(&mut self) {
+        let package_dirs = &self.package_dirs;
+        self.dir_suffixes.retain(|_, dirs| {
+            dirs.retain(|dir, _| package_dirs.contains_key(dir));
+            !dirs.is_empty()
+        });
+    }
+
+    /// Reads the module path and `replace` directives of a `gno.mod` file.
+    ///
+    /// Imports of a replaced path are redirected to the replacement, so that
+    /// an import of `gno.land/p/demo/foo` replaced by `./foo` resolves to the
+    /// package analyzed from that local directory.
+    ///
+    /// Packages analyzed afterwards from below the `gno.mod` directory are
+    /// keyed by their import path, the module path joined with their
+    /// directory relative to the module root, rather than by their package
+    /// clause name, which stays their `name`. `analyze_directory` reads
+    /// every `gno.mod` before any source file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the `gno.mod` file
+    pub fn analyze_gno_mod(&mut self, path: &Path) -> Result<(), AnalyzeError> {
+        let contents = std::fs::read_to_string(path)?;
+        self.apply_gno_mod(path, &contents);
+        Ok(())
+    }
+
+    /// Registers the module path and `replace` directives of a `gno.mod`
+    /// file read from `path`
+    fn apply_gno_mod(&mut self, path: &Path, contents: &str) {
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        if let Some(module) = parse_module_directive(contents) {
+            self.modules.insert(normalize_dir(base_dir), module);
+        }
+
+        for (old, new) in parse_replace_directives(contents) {
+            let replacement = if new.starts_with("./") || new.starts_with("../") || new == "." {
+                Replacement::Directory(normalize_dir(&base_dir.join(&new)))
+            } else if Path::new(&new).is_absolute() {
+                Replacement::Directory(normalize_dir(Path::new(&new)))
+            } else {
+                Replacement::ImportPath(new)
+            };
+            self.replacements.insert(old, replacement);
+        }
+        self.coupling_cache = None;
+    }
+
+    /// Resolves an import path to the name of the analyzed package it refers to.
+    ///
+    /// An import is looked up, in order:
+    ///  1. as a package key, see `Package::key`
+    ///  2. through a `replace` directive
+    ///  3. as the directory of a package, matching as many trailing segments
+    ///     of the directory's import path as possible, so
+    ///     `gno.land/p/demo/avl` resolves to the package declared in
+    ///     `.../p/demo/avl` whatever its name. A match must span at least
+    ///     two segments, or the directory's whole import path below a
+    ///     longer import, so `errors` or `encoding/json` never resolve to
+    ///     an analyzed `errs/errors` or `util/json`. Among directories
+    ///     matching equally well, the first by path wins.
+    ///
+    /// Returns `None` for imports of packages outside the analyzed set.
+    fn resolve_import(&self, import: &str) -> Option<&str> {
+        if let Some((name, _)) = self.packages.get_key_value(import) {
+            return Some(name);
+        }
+
+        // Of overlapping replaced paths, the longest matching one applies
+        for (old, replacement) in self.replacements.iter().rev() {
+            let rest = if import == old {
+                ""
+            } else if let Some(rest) = import
+                .strip_prefix(old.as_str())
+                .and_then(|rest| rest.strip_prefix('/'))
+            {
+                rest
+            } else {
+                continue;
+            };
+
+            let name = match replacement {
+                Replacement::Directory(dir) => {
+                    self.package_dirs.get(&normalize_dir(&dir.join(rest)))
+                }
+                Replacement::ImportPath(path) if rest.is_empty() => Some(path),
+                Replacement::ImportPath(path) => {
+                    let target = format!("{}/{}", path, rest);
+                    return self
+                        .packages
+                        .get_key_value(&target)
+                        .map(|(name, _)| name.as_str());
+                }
+            };
+            return name
+                .and_then(|name| self.packages.get_key_value(name))
+                .map(|(name, _)| name.as_str());
+        }
+
+        let segments: Vec<&str> = import.split('/').collect();
+        for start in 0..segments.len() {
+            let Some(dirs) = self.dir_suffixes.get(&segments[start..].join("/")) else {
+                continue;
+            };
+            let matched = segments.len() - start;
+            let name = dirs
+                .iter()
+                .filter(|&(_, &whole)| matched >= 2 || (whole && start > 0))
+                .find_map(|(dir, _)| self.package_dirs.get(dir))
+                .and_then(|name| self.packages.get_key_value(name));
+            if let Some((name, _)) = name {
+                return Some(name);
+            }
+        }
+        None
+    }
+
+    /// Drops every package whose import path isn't below one of `prefixes`.
+    ///
+    /// A package's import paths are its name and every import string that
+    /// resolves to it, e.g. through a `gno.mod` replacement. Prefixes match
+    /// whole path segments, so `gno.land/r/demo` matches `gno.land/r/demo/foo`
+    /// but not `gno.land/r/demos`. Imports of dropped packages stay on the
+    /// remaining packages and are treated as external from then on. An empty
+    /// list of prefixes keeps everything.
+    pub fn restrict_to_prefixes(&mut self, prefixes: &[String]) {
+        if prefixes.is_empty() {
+            return;
+        }
+        let prefixes: Vec<String> = prefixes
+            .iter()
+            .map(|prefix| normalize_import_path(prefix))
+            .collect();
+        let matches = |path: &str| {
+            prefixes.iter().any(|prefix| {
+                path.strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+        };
+
+        let mut keep: Set<String> = self
+            .packages
+            .keys()
+            .filter(|name| matches(name))
+            .cloned()
+            .collect();
+        for package in self.packages.values() {
+            for import in package.imports.iter().filter(|import| matches(import)) {
+                if let Some(name) = self.resolve_import(import) {
+                    keep.insert(name.to_string());
+                }
+            }
+        }
+
+        self.packages.retain(|name, _| keep.contains(name));
+        self.package_dirs.retain(|_, name| keep.contains(name));
+        self.prune_dir_suffixes();
+        self.coupling_cache = None;
+    }
+
+    /// Import path of a package directory below a `gno.mod` module root,
+    /// using the innermost module; `None` outside every module
+    fn module_import_path(&self, dir: &Path) -> Option<String> {
+        let (root, module) = self
+            .modules
+            .iter()
+            .filter(|(root, _)| dir.starts_with(root))
+            .max_by_key(|(root, _)| root.components().count())?;
+
+        let mut path = module.clone();
+        for component in dir.strip_prefix(root).ok()?.components() {
+            if let Component::Normal(segment) = component {
+                path.push('/');
+                path.push_str(&segment.to_string_lossy());
+            }
+        }
+        Some(path)
+    }
+
+    /// Expresses a package directory as a `/`-separated import path,
+    /// relative to the innermost analyzed root containing it. A package at
+    /// a root is named after the root's directory.
+    fn dir_import_path(&self, dir: &Path) -> String {
+        let segments = |path: &Path| -> Vec<String> {
+            path.components()
+                .filter_map(|component| match component {
+                    Component::Normal(segment) => Some(segment.to_string_lossy().into_owned()),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let root = self
+            .roots
+            .iter()
+            .filter(|root| dir.starts_with(root))
+            .max_by_key(|root| root.components().count());
+        let Some(root) = root else {
+            return segments(dir).join("/");
+        };
+        let relative = segments(dir.strip_prefix(root).unwrap_or(dir));
+        if relative.is_empty() {
+            segments(root).pop().unwrap_or_default()
+        } else {
+            relative.join("/")
+        }
+    }
+
+    /// Maps each package name to the directory its files were found in
+    fn package_directories(&self) -> Map<&str, &Path> {
+        self.package_dirs
+            .iter()
+            .map(|(dir, name)| (name.as_str(), dir.as_path()))
+            .collect()
+    }
+
+    /// Extracts package name, imports and exported functions from Go source code
+    fn extract_source_info(&self, source_code: &str) -> Result<SourceInfo, AnalyzeError> {
+        let mut parser = Parser::new();
+        let language = tree_sitter_go::LANGUAGE;
+        parser.set_language(&language.into())?;
+
+        let tree = parser
+            .parse(source_code, None)
+            .ok_or_else(|| AnalyzeError::Parse("Failed to parse source code".to_string()))?;
+
+        // Query for package clause and imports
+        let query = Query::new(&language.into(), SOURCE_QUERY)?;
+
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&query, tree.root_node(), source_code.as_bytes());
+
+        let mut info = SourceInfo {
+            ast_nodes: count_nodes(&tree),
+            syntax_errors: find_syntax_errors(tree.root_node(), source_code),
+            generated: is_generated_source(source_code),
+            build_constraint: BuildConstraint::from_source(source_code),
+            ..Default::default()
+        };
+
+        while let Some(matched) = matches.next_mut() {
+            for capture in matched.captures {
+                let capture_text = capture
+                    .node
+                    .utf8_text(source_code.as_bytes())?
+                    .trim_matches(|c| c == '"' || c == '`');
+
+                match query.capture_names()[capture.index as usize] {
+                    "package" => {
+                        info.package = capture_text.to_string();
+                    }
+                    name @ ("import" | "grouped_import") => {
+                        let field = |field| {
+                            capture
+                                .node
+                                .child_by_field_name(field)
+                                .map(|node| node.utf8_text(source_code.as_bytes()))
+                                .transpose()
+                        };
+                        let path = field("path")?.unwrap_or_default();
+                        let alias = field("name")?;
+                        info.imports.push(ImportSpec {
+                            path: path.trim_matches(|c| c == '"' || c == '`').to_string(),
+                            alias: alias.map(str::to_string),
+                            kind: ImportKind::from_alias(alias),
+                            form: if name == "import" {
+                                ImportForm::Single
+                            } else {
+                                ImportForm::Grouped
+                            },
+                        });
+                    }
+                    "function" if capture_text.starts_with(char::is_uppercase) => {
+                        info.exported_functions += 1;
+                    }
+                    "qualifier" => {
+                        *info.qualifiers.entry(capture_text.to_string()).or_default() += 1;
+                    }
+                    "type" => info.types.types += 1,
+                    "interface" => info.types.interfaces += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(info)
+    }
+
+    /// Calculates coupling scores for all analyzed packages.
+    ///
+    /// For each package, computes:
+    ///  1. Afferent coupling (Ca) - number of packages that depend on it
+    ///  2. Efferent coupling (Ce) - number of packages it depends on
+    ///  3. Instability (I) = Ce/(Ca+Ce)
+    ///
+    /// A higher score (closer to 1.0) indicates that the package is more unstable
+    /// and dependent on other packages.
+    pub fn calculate_coupling_scores(&mut self) {
+        let counts = self.compute_coupling_counts();
+
+        // Update coupling scores for each package
+        for package in self.packages.values_mut() {
+            let package_counts = counts.get(&package.key).copied().unwrap_or_default();
+            package.isolated = package_counts.afferent + package_counts.efferent == 0;
+            package.coupling_score = package_counts.instability(self.external_weight);
+        }
+
+        self.coupling_cache = Some(counts);
+    }
+
+    /// Computes the coupling score of every package like
+    /// `calculate_coupling_scores`, without storing anything.
+    ///
+    /// Scores reflect the current files and settings even when
+    /// `calculate_coupling_scores` hasn't been called since they changed,
+    /// so a shared analyzer can be queried without being mutated.
+    ///
+    /// # Returns
+    ///
+    /// * Instability by package name
+    pub fn coupling_scores(&self) -> HashMap<String, f64> {
+        self.coupling_counts()
+            .iter()
+            .map(|(name, counts)| (name.clone(), counts.instability(self.external_weight)))
+            .collect()
+    }
+
+    /// Whether the coupling scores still reflect every analyzed file and
+    /// scoring setting.
+    ///
+    /// Meant for callers pushing files one at a time: analyzing a file or
+    /// `gno.mod`, restricting the packages or changing a scoring setting
+    /// marks the scores stale, and only `calculate_coupling_scores` brings
+    /// them up to date again. This is a flag check, so it is cheap to call
+    /// after every file.
+    pub fn scores_up_to_date(&self) -> bool {
+        self.coupling_cache.is_some()
+    }
+
+    /// Returns the afferent/efferent counts of every package, reusing the
+    /// counts cached by `calculate_coupling_scores` when they are still valid
+    fn coupling_counts(&self) -> Cow<'_, Map<String, CouplingCounts>> {
+        match &self.coupling_cache {
+            Some(counts) => Cow::Borrowed(counts),
+            None => Cow::Owned(self.compute_coupling_counts()),
+        }
+    }
+
+    /// Computes afferent and efferent counts for all packages
+    fn compute_coupling_counts(&self) -> Map<String, CouplingCounts> {
+        let ignored = if self.ignore_cycle_edges {
+            self.cycle_edges()
+        } else {
+            Set::default()
+        };
+        let afferent = self.calculate_afferent_coupling(&ignored);
+
+        self.packages
+            .values()
+            .map(|package| {
+                let imports: Vec<&String> = package
+                    .imports
+                    .iter()
+                    .filter(|import| !self.is_discounted_import(import))
+                    .filter(|import| {
+                        !(self.exclude_blank_imports && package.is_blank_import(import))
+                    })
+                    .filter(|import| {
+                        self.resolve_import(import)
+                            .is_none_or(|target| !ignored.contains(&(package.key.as_str(), target)))
+                    })
+                    .collect();
+                let counts = CouplingCounts {
+                    afferent: afferent.get(&package.key).copied().unwrap_or(0),
+                    efferent: imports.len(),
+                    external: imports
+                        .iter()
+                        .filter(|import| self.resolve_import(import).is_none())
+                        .count(),
+                };
+                (package.key.clone(), counts)
             })
             .collect()
     }
 
+    /// Calculate afferent coupling for all packages
+    ///
+    /// Every import is resolved once and credited to its target, so this
+    /// runs in O(total imports) rather than checking every package against
+    /// the imports of every other package. A package importing the same
+    /// target through several paths still counts once. Imports listed in
+    /// `ignored` as `(importer, imported)` pairs aren't credited.
+    fn calculate_afferent_coupling(&self, ignored: &Set<(&str, &str)>) -> Map<String, usize> {
+        let mut afferent: Map<String, usize> =
+            self.packages.keys().map(|name| (name.clone(), 0)).collect();
+
+        for package in self.packages.values() {
+            let targets: Set<&str> = package
+                .imports
+                .iter()
+                .filter_map(|import| self.resolve_import(import))
+                .collect();
+            for target in targets {
+                if !self.is_discounted_import(target)
+                    && !ignored.contains(&(package.key.as_str(), target))
+                    && let Some(count) = afferent.get_mut(target)
+                {
+                    *count += 1;
+                }
+            }
+        }
+
+        afferent
+    }
+
+    /// Returns the imports of a package that point at generated-only packages
+    fn generated_imports(&self, package: &Package) -> Vec<String> {
+        let mut imports: Vec<String> = package
+            .imports
+            .iter()
+            .filter(|import| {
+                self.resolve_import(import)
+                    .and_then(|name| self.packages.get(name))
+                    .is_some_and(Package::is_generated)
+            })
+            .cloned()
+            .collect();
+        imports.sort();
+        imports
+    }
+
+    /// Whether an import is left out of the coupling counts because
+    /// `set_discount_generated_imports` is on and it targets generated code
+    fn is_discounted_import(&self, import: &str) -> bool {
+        self.discount_generated_imports
+            && self
+                .resolve_import(import)
+                .and_then(|name| self.packages.get(name))
+                .is_some_and(Package::is_generated)
+    }
+
     /// Returns a vector of package references sorted by coupling score in descending order.
     ///
     /// Packages with higher coupling scores (more unstable) appear first in the result.
+    /// Isolated packages have no meaningful score and come last, after even
+    /// the most stable connected packages.
     pub fn get_sorted_packages(&self) -> Vec<&Package> {
         let mut packages: Vec<&Package> = self.packages.values().collect();
 
         packages.sort_by(|a, b| {
-            b.coupling_score
-                .partial_cmp(&a.coupling_score)
-                .unwrap_or(std::cmp::Ordering::Equal)
+            a.isolated.cmp(&b.isolated).then_with(|| {
+                b.coupling_score
+                    .partial_cmp(&a.coupling_score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
         });
         packages
     }
 
+    /// Returns the packages whose instability lies within `[min, max]`.
+    ///
+    /// Both bounds are inclusive. Packages come back in the same order as
+    /// `get_sorted_packages`, most unstable first. Isolated packages have no
+    /// instability and are never included.
+    pub fn packages_in_range(&self, min: f64, max: f64) -> Result<Vec<&Package>, AnalyzeError> {
+        if min.is_nan() || max.is_nan() || min > max {
+            return Err(AnalyzeError::InvalidRange(format!(
+                "min ({}) must not exceed max ({})",
+                min, max
+            )));
+        }
+
+        let mut packages = self.get_sorted_packages();
+        packages.retain(|p| !p.isolated && (min..=max).contains(&p.coupling_score));
+        Ok(packages)
+    }
+
+    /// Maps each package to the source files that declare it.
+    ///
+    /// Packages are keyed the same way as everywhere else in the analyzer;
+    /// file lists are sorted.
+    pub fn index(&self) -> HashMap<String, Vec<PathBuf>> {
+        self.packages
+            .values()
+            .map(|package| {
+                let mut files = package.files.clone();
+                files.sort();
+                (package.key.clone(), files)
+            })
+            .collect()
+    }
+
+    /// Maps each package to its imports that don't resolve to an analyzed
+    /// package, such as the standard library and external modules.
+    ///
+    /// These imports play no part in `generate_deployment_order`. Packages
+    /// whose imports all resolve are left out; import lists are sorted.
+    pub fn unresolved_imports(&self) -> HashMap<String, Vec<String>> {
+        self.packages
+            .values()
+            .filter_map(|package| {
+                let mut imports: Vec<String> = package
+                    .imports
+                    .iter()
+                    .filter(|import| self.resolve_import(import).is_none())
+                    .cloned()
+                    .collect();
+                if imports.is_empty() {
+                    return None;
+                }
+                imports.sort();
+                Some((package.key.clone(), imports))
+            })
+            .collect()
+    }
+
+    /// Lists every analyzed package, one per line, sorted by name.
+    ///
+    /// No metrics are computed, so this is cheap to call right after the files
+    /// have been analyzed.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Optional glob pattern that package names must match
+    /// * `with_imports` - List each package's imports below its name
+    pub fn list_packages(
+        &self,
+        filter: Option<&str>,
+        with_imports: bool,
+    ) -> Result<String, AnalyzeError> {
+        let pattern = filter
+            .map(glob::Pattern::new)
+            .transpose()
+            .map_err(|e| AnalyzeError::InvalidPattern(e.to_string()))?;
+
+        let mut packages: Vec<&Package> = self
+            .packages
+            .values()
+            .filter(|p| pattern.as_ref().is_none_or(|pat| pat.matches(&p.key)))
+            .collect();
+        packages.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let mut output = String::new();
+        for package in packages {
+            output.push_str(&package.key);
+            output.push('\n');
+
+            if with_imports {
+                let mut imports: Vec<&String> = package.imports.iter().collect();
+                imports.sort();
+                for import in imports {
+                    output.push_str(&format!("  - {}\n", import));
+                }
+            }
+        }
+        Ok(output)
+    }
+
     /// Generates a deployment order based on topological sorting of package dependencies.
     ///
     /// The implementation uses Kahn's algorithm for topological sorting, which:
@@ -215,6 +1854,11 @@ impl DependencyAnalyzer {
     /// 2. Removes these nodes and their outgoing edges from the graph
     /// 3. Repeats until all nodes are processed or a cycle is detected
     ///
+    /// Among packages that are ready at the same time, those with a higher
+    /// weight from `set_priorities` go first; equal weights keep the order in
+    /// which the packages became ready. Weights never move a package ahead of
+    /// its dependencies.
+    ///
     /// # Returns
     ///
     /// * A vector of package references in deployment order (dependencies first)
@@ -228,33 +1872,49 @@ impl DependencyAnalyzer {
         // Build dependency graph
         let (dependency_count, dependents) = self.build_dependency_graph();
 
+        // Ready packages, highest weight first, then first come first served.
+        // Packages becoming ready at the same time are pushed in name order,
+        // so ties don't depend on map iteration order.
+        let mut queue = BinaryHeap::new();
+        let mut ready = 0;
+        let mut push = |queue: &mut BinaryHeap<_>, mut names: Vec<_>| {
+            names.sort_unstable();
+            for name in names {
+                queue.push((self.priorities.weight(name), Reverse(ready), name));
+                ready += 1;
+            }
+        };
+
         // Start with packages that have no dependencies
-        let mut queue: VecDeque<&str> = dependency_count
+        let roots = dependency_count
             .iter()
             .filter(|(_, count)| **count == 0)
             .map(|(&name, _)| name)
             .collect();
+        push(&mut queue, roots);
 
         let mut result: Vec<&Package> = Vec::new();
         let mut remaining_dependencies = dependency_count.clone();
 
         // Process packages with no dependencies
-        while let Some(package_name) = queue.pop_front() {
+        while let Some((_, _, package_name)) = queue.pop() {
             if let Some(package) = self.packages.get(package_name) {
                 result.push(package);
             }
 
             // For all packages that depend on this one
+            let mut unblocked = Vec::new();
             if let Some(deps) = dependents.get(package_name) {
                 for &dependent in deps {
                     if let Some(count) = remaining_dependencies.get_mut(dependent) {
                         *count -= 1;
                         if *count == 0 {
-                            queue.push_back(dependent);
+                            unblocked.push(dependent);
                         }
                     }
                 }
             }
+            push(&mut queue, unblocked);
         }
 
         // Handle cyclic dependencies if any
@@ -263,24 +1923,151 @@ impl DependencyAnalyzer {
         result
     }
 
+    /// Groups packages into deployment waves.
+    ///
+    /// Every package in a wave depends only on packages from earlier waves, so
+    /// the packages of one wave can be deployed in parallel while the waves
+    /// themselves are deployed in sequence. Packages within a wave are sorted
+    /// by name.
+    ///
+    /// # Warning
+    ///
+    /// Packages that are part of, or depend on, a cyclic dependency can't be
+    /// ordered and are placed together in a final wave.
+    pub fn deployment_waves(&self) -> Vec<Vec<&Package>> {
+        let (dependency_count, dependents) = self.build_dependency_graph();
+        let mut remaining_dependencies = dependency_count.clone();
+
+        let mut current: Vec<&str> = dependency_count
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(&name, _)| name)
+            .collect();
+
+        let mut waves: Vec<Vec<&Package>> = Vec::new();
+        let mut placed = 0;
+
+        while !current.is_empty() {
+            current.sort();
+            let mut next = Vec::new();
+
+            for &package_name in &current {
+                if let Some(deps) = dependents.get(package_name) {
+                    for &dependent in deps {
+                        if let Some(count) = remaining_dependencies.get_mut(dependent) {
+                            *count -= 1;
+                            if *count == 0 {
+                                next.push(dependent);
+                            }
+                        }
+                    }
+                }
+            }
+
+            placed += current.len();
+            waves.push(
+                current
+                    .iter()
+                    .filter_map(|name| self.packages.get(*name))
+                    .collect(),
+            );
+            current = next;
+        }
+
+        if placed < self.packages.len() {
+            let mut cyclic: Vec<&Package> = remaining_dependencies
+                .iter()
+                .filter(|(_, count)| **count > 0)
+                .filter_map(|(name, _)| self.packages.get(*name))
+                .collect();
+            cyclic.sort_by(|a, b| a.key.cmp(&b.key));
+            waves.push(cyclic);
+        }
+
+        waves
+    }
+
+    /// Exports the deployment plan in the specified format
+    ///
+    /// Supported formats:
+    /// - `gha-matrix`: a GitHub Actions job matrix with one entry per wave
+    /// - `dot-deploy`: a Graphviz digraph of the deployment DAG, one rank per wave
+    /// - `json`: the deployment order as a JSON array of package names
+    /// - `text`: the deployment order, one package per line
+    pub fn export_deployment_plan(&self, format: &str) -> Result<String, AnalyzeError> {
+        let order = || -> Vec<&str> {
+            self.generate_deployment_order()
+                .iter()
+                .map(|package| package.key.as_str())
+                .collect()
+        };
+        match format {
+            "gha-matrix" => {
+                let waves: Vec<Vec<&str>> = self
+                    .deployment_waves()
+                    .iter()
+                    .map(|wave| wave.iter().map(|p| p.key.as_str()).collect())
+                    .collect();
+                Ok(serde_json::to_string_pretty(
+                    &serde_json::json!({ "wave": waves }),
+                )?)
+            }
+            "dot-deploy" => Ok(self.format_deployment_dot()),
+            "json" => Ok(serde_json::to_string_pretty(&order())?),
+            "text" => Ok(order().join("\n")),
+            _ => Err(AnalyzeError::UnsupportedFormat(format.to_string())),
+        }
+    }
+
+    /// Formats the deployment DAG as a Graphviz digraph.
+    ///
+    /// Edges point from each dependency to its dependent, i.e. in deployment
+    /// direction, and packages of the same wave share a `rank=same` group.
+    fn format_deployment_dot(&self) -> String {
+        let mut output = String::from("digraph deployment {\n");
+
+        for wave in self.deployment_waves() {
+            let nodes: Vec<String> = wave
+                .iter()
+                .map(|p| format!("\"{}\";", escape_dot(&p.key)))
+                .collect();
+            output.push_str(&format!("  {{ rank=same; {} }}\n", nodes.join(" ")));
+        }
+
+        let mut names: Vec<&String> = self.packages.keys().collect();
+        names.sort();
+        for name in names {
+            for dependency in self.internal_imports(name) {
+                output.push_str(&format!(
+                    "  \"{}\" -> \"{}\";\n",
+                    escape_dot(dependency),
+                    escape_dot(name)
+                ));
+            }
+        }
+
+        output.push_str("}\n");
+        output
+    }
+
     /// Builds the dependency graph for topological sorting
-    fn build_dependency_graph(&self) -> (HashMap<&str, usize>, HashMap<&str, Vec<&str>>) {
-        let mut dependency_count: HashMap<&str, usize> = HashMap::new();
-        let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+    fn build_dependency_graph(&self) -> (Map<&str, usize>, Map<&str, Vec<&str>>) {
+        let mut dependency_count: Map<&str, usize> = Map::default();
+        let mut dependents: Map<&str, Vec<&str>> = Map::default();
 
         // Initialize for all packages
         for package in self.packages.values() {
-            dependency_count.insert(&package.name, 0);
-            dependents.insert(&package.name, vec![]);
+            dependency_count.insert(&package.key, 0);
+            dependents.insert(&package.key, vec![]);
         }
 
         // Count dependencies: if A imports B, A depends on B
         for package in self.packages.values() {
-            let dependent_name = &package.name;
+            let dependent_name = &package.key;
 
             // For each import, register it as a dependency of the current package
-            for dependency in &package.imports {
-                if self.packages.contains_key(dependency) {
+            for import in &package.imports {
+                if let Some(dependency) = self.resolve_import(import) {
                     // This package depends on the imported package
                     *dependency_count.entry(dependent_name).or_insert(0) += 1;
 
@@ -296,68 +2083,244 @@ impl DependencyAnalyzer {
         (dependency_count, dependents)
     }
 
-    /// Handles adding packages involved in cyclic dependencies to the result
+    /// Handles adding packages involved in cyclic dependencies to the
+    /// result. The cycles aren't reported here; callers that want to warn
+    /// about them use `find_cycles`.
     fn handle_cyclic_dependencies<'a>(
         &'a self,
         result: &mut Vec<&'a Package>,
-        remaining_dependencies: &HashMap<&str, usize>,
+        remaining_dependencies: &Map<&str, usize>,
     ) {
         if result.len() < self.packages.len() {
-            eprintln!(
-                "Warning: Cyclic dependencies detected. Deployment order may not be optimal."
-            );
-
-            // Add remaining packages (those involved in cycles)
-            for (name, &count) in remaining_dependencies {
-                if count > 0 {
-                    if let Some(package) = self.packages.get(*name) {
-                        if !result.contains(&package) {
-                            result.push(package);
-                        }
-                    }
+            // Add remaining packages (those involved in cycles), by name
+            let mut remaining: Vec<(&&str, &usize)> = remaining_dependencies.iter().collect();
+            remaining.sort_unstable();
+            for (name, &count) in remaining {
+                if count > 0
+                    && let Some(package) = self.packages.get(*name)
+                    && !result.contains(&package)
+                {
+                    result.push(package);
                 }
             }
         }
     }
 
     /// Exports analysis results in the specified format
+    ///
+    /// Package reports go through a fixed pipeline, so combined options
+    /// always interact the same way:
+    ///
+    ///  1. Every package is evaluated, most unstable first
+    ///  2. Filters drop packages, see `report_filters` for their order
+    ///  3. The survivors are sorted by `sort_by`, or ranked by `score_formula`
+    ///  4. The list is truncated to `limit`
+    ///
+    /// Graph formats always show every package, so asking them for a
+    /// filtered report is an error rather than silently ignored.
     pub fn export_analysis(
         &self,
         format: &str,
-        detailed: bool,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let packages = self.get_sorted_packages();
-        let results = self.prepare_analysis_results(&packages);
+        options: &ExportOptions,
+    ) -> Result<String, AnalyzeError> {
+        if GRAPH_FORMATS.contains(&format) {
+            let filters = options.package_filters();
+            if !filters.is_empty() {
+                return Err(AnalyzeError::UnsupportedOption(format!(
+                    "{} output always covers every package and can't apply {}",
+                    format,
+                    filters.join(", ")
+                )));
+            }
+        }
+
+        let filters = self.report_filters(options)?;
+        let mut results =
+            self.prepare_analysis_results(&self.get_sorted_packages(), options.detailed);
+        results.retain(|result| filters.iter().all(|keep| keep(result)));
+
+        match options.sort_by {
+            SortKey::Instability => {}
+            SortKey::ApiSurface => results.sort_by(|a, b| {
+                b.metrics
+                    .exported_functions
+                    .cmp(&a.metrics.exported_functions)
+            }),
+            SortKey::BusRisk => results.sort_by(|a, b| {
+                let risk = |result: &PackageAnalysis| result.metrics.bus_factor_risk.unwrap_or(0.0);
+                risk(b).total_cmp(&risk(a))
+            }),
+        }
+
+        if let Some(formula) = &options.score_formula {
+            self.apply_score_formula(formula, &mut results)?;
+        }
+
+        if let Some(limit) = options.limit {
+            results.truncate(limit);
+        }
 
         match format {
-            "json" => Ok(serde_json::to_string_pretty(&results)?),
-            "text" => Ok(self.format_text_output(&results, detailed)),
-            _ => Err(AnalysisError::UnsupportedFormat(format.to_string()).into()),
+            "json" => match &options.meta {
+                Some(meta) => Ok(serde_json::to_string_pretty(&serde_json::json!({
+                    "meta": meta,
+                    "summary": self.report_summary(),
+                    "packages": results,
+                }))?),
+                None => Ok(serde_json::to_string_pretty(&results)?),
+            },
+            "yaml" => match &options.meta {
+                Some(meta) => Ok(serde_yaml::to_string(&serde_json::json!({
+                    "meta": meta,
+                    "summary": self.report_summary(),
+                    "packages": results,
+                }))?),
+                None => Ok(serde_yaml::to_string(&results)?),
+            },
+            "text" => Ok(self.format_text_output(&results, options.detailed)),
+            "opml" => Ok(self.format_opml_output()),
+            "communities" => Ok(self.format_communities_output()),
+            "hierarchy" => Ok(self.format_hierarchy_output()),
+            "dgml" => Ok(self.format_dgml_output()),
+            "nix" => Ok(self.format_nix_output()),
+            "flamegraph" => Ok(self.format_flamegraph_output()),
+            "deps-manifest" => Ok(self.format_deps_manifest(&results)?),
+            "d3" => Ok(self.format_d3_output()?),
+            "dot" => Ok(self.format_dot_output(options.legend)),
+            "mermaid" => Ok(self.format_mermaid_output(options.legend)),
+            "csv" => Ok(self.format_csv_output(&results)),
+            "html" => Ok(self.format_html_output(&results)),
+            _ => Err(AnalyzeError::UnsupportedFormat(format.to_string())),
+        }
+    }
+
+    /// Project-wide figures written next to the package list
+    pub fn report_summary(&self) -> ReportSummary {
+        ReportSummary {
+            modularity: self.modularity(),
+        }
+    }
+
+    /// Builds the report filters selected by `options`; a package is
+    /// reported only if every filter keeps it.
+    ///
+    /// Filters are listed in the order they are applied:
+    ///  1. `hide_leaf_utilities`
+    ///  2. `exclude_packages`
+    ///  3. `min_score`
+    ///  4. `zone`
+    ///  5. `tags`
+    fn report_filters(
+        &self,
+        options: &ExportOptions,
+    ) -> Result<Vec<ReportFilter<'_>>, AnalyzeError> {
+        let mut filters: Vec<ReportFilter<'_>> = Vec::new();
+
+        if options.hide_leaf_utilities {
+            filters.push(Box::new(|result| !self.is_leaf_utility(result)));
+        }
+
+        if !options.exclude_packages.is_empty() {
+            let patterns = options
+                .exclude_packages
+                .iter()
+                .map(|pattern| glob::Pattern::new(pattern))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| AnalyzeError::InvalidPattern(e.to_string()))?;
+            filters.push(Box::new(move |result| {
+                !patterns
+                    .iter()
+                    .any(|pattern| pattern.matches(&result.name) || pattern.matches(&result.key))
+            }));
+        }
+
+        if let Some(min) = options.min_score {
+            let in_range: Set<&str> = self
+                .packages_in_range(min, 1.0)?
+                .into_iter()
+                .map(|package| package.key.as_str())
+                .collect();
+            filters.push(Box::new(move |result| {
+                in_range.contains(result.key.as_str())
+            }));
+        }
+
+        if let Some(zone) = options.zone {
+            filters.push(Box::new(move |result| {
+                Zone::of(result.isolated, result.coupling_score) == zone
+            }));
+        }
+
+        if !options.tags.is_empty() {
+            let tags = options.tags.clone();
+            filters.push(Box::new(move |result| {
+                result.tags.iter().any(|tag| tags.contains(tag))
+            }));
         }
+
+        Ok(filters)
+    }
+
+    /// Returns true if the package imports nothing internal and nothing
+    /// internal imports it
+    fn is_leaf_utility(&self, result: &PackageAnalysis) -> bool {
+        result.metrics.afferent_coupling == 0
+            && result
+                .imports
+                .iter()
+                .all(|import| self.resolve_import(import).is_none())
     }
 
     /// Prepares analysis results from packages
-    fn prepare_analysis_results(&self, packages: &[&Package]) -> Vec<PackageAnalysis> {
+    ///
+    /// Import specs are only attached when `detailed` is set.
+    fn prepare_analysis_results(
+        &self,
+        packages: &[&Package],
+        detailed: bool,
+    ) -> Vec<PackageAnalysis> {
+        let counts = self.coupling_counts();
+        let churn_loaded = self.churn.is_some();
+        let directions = if detailed {
+            self.edge_directions()
+        } else {
+            Map::default()
+        };
+
         packages
             .iter()
             .map(|p| {
-                let afferent = self
-                    .packages
-                    .values()
-                    .filter(|other| other.imports.contains(&p.name))
-                    .count();
+                let package_counts = counts.get(&p.key).copied().unwrap_or_default();
+                let abstractness = p.abstractness();
+                let mut imports: Vec<String> = p.imports.iter().cloned().collect();
+                imports.sort();
 
                 PackageAnalysis {
                     name: p.name.clone(),
+                    key: p.key.clone(),
+                    import_path: p.import_path.clone(),
                     coupling_score: p.coupling_score,
-                    imports: p.imports.iter().cloned().collect(),
+                    isolated: p.isolated,
+                    imports,
+                    tags: self.package_tags(&p.key),
+                    import_specs: detailed.then(|| p.import_specs.clone()),
+                    generated_imports: self.generated_imports(p),
                     metrics: DetailedMetrics {
-                        afferent_coupling: afferent,
-                        efferent_coupling: p.imports.len(),
+                        afferent_coupling: package_counts.afferent,
+                        efferent_coupling: package_counts.efferent,
                         instability: p.coupling_score,
-                        abstractness: 0.0, // TODO: Implement
-                        distance: 0.0,     // TODO: Implement
+                        abstractness,
+                        distance: (abstractness + p.coupling_score - 1.0).abs(),
+                        lines_of_code: p.lines_of_code,
+                        exported_functions: p.exported_functions,
+                        ast_nodes: p.ast_nodes(),
+                        weighted_efferent: p.weighted_efferent(),
+                        edge_directions: directions.get(p.key.as_str()).copied(),
+                        churn: churn_loaded.then(|| self.churn(&p.key)),
+                        bus_factor_risk: churn_loaded
+                            .then(|| self.bus_factor_risk(&p.key, package_counts.afferent)),
                     },
+                    score: None,
                 }
             })
             .collect()
@@ -368,7 +2331,20 @@ impl DependencyAnalyzer {
         let mut output = String::new();
         for result in results {
             output.push_str(&format!("Package: {}\n", result.name));
-            output.push_str(&format!("Coupling Score: {:.2}\n", result.coupling_score));
+            if result.isolated {
+                output.push_str("Coupling Score: n/a (isolated)\n");
+            } else {
+                output.push_str(&format!("Coupling Score: {:.2}\n", result.coupling_score));
+            }
+            if let Some(score) = result.score {
+                output.push_str(&format!("Score: {:.2}\n", score));
+            }
+            if !result.tags.is_empty() {
+                output.push_str(&format!("Tags: {}\n", result.tags.join(", ")));
+            }
+            if result.import_path != result.name {
+                output.push_str(&format!("Import Path: {}\n", result.import_path));
+            }
 
             if detailed {
                 output.push_str(&format!(
@@ -379,6 +2355,29 @@ impl DependencyAnalyzer {
                     "Efferent Coupling: {}\n",
                     result.metrics.efferent_coupling
                 ));
+                output.push_str(&format!(
+                    "Weighted Efferent Coupling: {}\n",
+                    result.metrics.weighted_efferent
+                ));
+                output.push_str(&format!(
+                    "Distance from Main Sequence: {:.2}\n",
+                    result.metrics.distance
+                ));
+                if let Some(directions) = result.metrics.edge_directions {
+                    output.push_str(&format!(
+                        "Edge Directions: {} downward, {} sideways, {} upward ({:.0}% downward)\n",
+                        directions.downward,
+                        directions.sideways,
+                        directions.upward,
+                        directions.downward_ratio * 100.0
+                    ));
+                }
+                if !result.generated_imports.is_empty() {
+                    output.push_str(&format!(
+                        "Generated Imports: {}\n",
+                        result.generated_imports.join(", ")
+                    ));
+                }
                 output.push_str("Imports:\n");
                 for import in &result.imports {
                     output.push_str(&format!("  - {}\n", import));
@@ -388,66 +2387,523 @@ impl DependencyAnalyzer {
         }
         output
     }
+
+    /// Formats the dependency tree as an OPML outline
+    ///
+    /// Each root package (one that no other analyzed package imports) becomes a
+    /// top-level `<outline>`, with the packages it imports nested as children.
+    /// Every package is expanded once, where it is first reached; see
+    /// `write_opml_outline`.
+    fn format_opml_output(&self) -> String {
+        let mut output = String::new();
+        output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        output.push_str("<opml version=\"2.0\">\n");
+        output.push_str("  <head>\n");
+        output.push_str("    <title>Dependency tree</title>\n");
+        output.push_str("  </head>\n");
+        output.push_str("  <body>\n");
+
+        let counts = self.coupling_counts();
+        let mut names: Vec<&str> = self.packages.keys().map(String::as_str).collect();
+        names.sort();
+
+        let mut roots: Vec<&str> = names
+            .iter()
+            .copied()
+            .filter(|name| counts.get(*name).is_none_or(|c| c.afferent == 0))
+            .collect();
+
+        let mut reached = Set::default();
+        for root in &roots {
+            self.collect_reachable(root, &mut reached);
+        }
+        // Packages only reachable through a cycle have no natural root
+        for name in &names {
+            if !reached.contains(name) {
+                roots.push(name);
+                self.collect_reachable(name, &mut reached);
+            }
+        }
+
+        let mut visited = Set::default();
+        for root in roots {
+            self.write_opml_outline(root, 2, &mut visited, &mut output);
+        }
+
+        output.push_str("  </body>\n");
+        output.push_str("</opml>\n");
+        output
+    }
+
+    /// Writes an outline node and, depth first, the internal dependencies
+    /// below it.
+    ///
+    /// A package is expanded only the first time it is written; later
+    /// occurrences are leaves, so shared dependencies don't multiply the
+    /// output and cycles terminate. An explicit stack keeps long import
+    /// chains from overflowing the call stack.
+    fn write_opml_outline<'a>(
+        &'a self,
+        root: &'a str,
+        depth: usize,
+        visited: &mut Set<&'a str>,
+        output: &mut String,
+    ) {
+        enum Step<'a> {
+            Open(&'a str, usize),
+            Close(usize),
+        }
+
+        let mut stack = vec![Step::Open(root, depth)];
+        while let Some(step) = stack.pop() {
+            let (name, depth) = match step {
+                Step::Open(name, depth) => (name, depth),
+                Step::Close(depth) => {
+                    output.push_str(&format!("{}</outline>\n", "  ".repeat(depth)));
+                    continue;
+                }
+            };
+
+            let indent = "  ".repeat(depth);
+            let children = self.internal_imports(name);
+            if children.is_empty() || !visited.insert(name) {
+                output.push_str(&format!(
+                    "{}<outline text=\"{}\"/>\n",
+                    indent,
+                    escape_xml(name)
+                ));
+                continue;
+            }
+
+            output.push_str(&format!(
+                "{}<outline text=\"{}\">\n",
+                indent,
+                escape_xml(name)
+            ));
+            stack.push(Step::Close(depth));
+            stack.extend(
+                children
+                    .into_iter()
+                    .rev()
+                    .map(|child| Step::Open(child, depth + 1)),
+            );
+        }
+    }
+
+    /// Returns the sorted imports of a package that refer to analyzed packages
+    fn internal_imports(&self, name: &str) -> Vec<&str> {
+        let mut imports: Vec<&str> = self
+            .packages
+            .get(name)
+            .map(|package| {
+                package
+                    .imports
+                    .iter()
+                    .filter_map(|import| self.resolve_import(import))
+                    .collect()
+            })
+            .unwrap_or_default();
+        imports.sort();
+        imports.dedup();
+        imports
+    }
+
+    /// Collects every package reachable from `name`, including itself
+    fn collect_reachable<'a>(&'a self, name: &'a str, reached: &mut Set<&'a str>) {
+        if !reached.insert(name) {
+            return;
+        }
+        for child in self.internal_imports(name) {
+            self.collect_reachable(child, reached);
+        }
+    }
+}
+
+/// File at a project root listing paths for `analyze_directory` to skip
+const DEPLOYIGNORE: &str = ".deployignore";
+
+/// Reads the glob patterns of the `.deployignore` file at `root`, if any.
+///
+/// One pattern per line, matched like `--exclude` patterns against paths
+/// relative to the root. Blank lines and lines starting with `#` are
+/// ignored, and a leading or trailing `/` is dropped, so `/vendor/` skips
+/// the `vendor` directory at the root.
+fn read_deployignore(root: &Path) -> Result<Vec<glob::Pattern>, AnalyzeError> {
+    let contents = match std::fs::read_to_string(root.join(DEPLOYIGNORE)) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    contents
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            glob::Pattern::new(line.trim_matches('/')).map_err(|e| {
+                AnalyzeError::InvalidPattern(format!("{} line {}: {}", DEPLOYIGNORE, number + 1, e))
+            })
+        })
+        .collect()
+}
+
+/// Parses the `module` directive from the contents of a `gno.mod` file
+fn parse_module_directive(contents: &str) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let line = line.split("//").next().unwrap_or("").trim();
+        let module = line.strip_prefix("module")?;
+        if !module.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let module = module.trim().trim_matches('"');
+        (!module.is_empty()).then(|| module.to_string())
+    })
+}
+
+/// Parses `replace old => new` directives from the contents of a `gno.mod`
+/// file, in both the single-line and the parenthesized block form.
+///
+/// Version suffixes on either side of the arrow are ignored.
+fn parse_replace_directives(contents: &str) -> Vec<(String, String)> {
+    let mut directives = Vec::new();
+    let mut in_block = false;
+
+    for line in contents.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+
+        let spec = if in_block {
+            if line == ")" {
+                in_block = false;
+                continue;
+            }
+            line
+        } else if let Some(rest) = line
+            .strip_prefix("replace")
+            .filter(|rest| rest.starts_with(|c: char| c.is_whitespace() || c == '('))
+        {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_block = true;
+                continue;
+            }
+            rest
+        } else {
+            continue;
+        };
+
+        if let Some((old, new)) = spec.split_once("=>") {
+            let old = old.split_whitespace().next();
+            let new = new.split_whitespace().next();
+            if let (Some(old), Some(new)) = (old, new) {
+                directives.push((old.to_string(), new.to_string()));
+            }
+        }
+    }
+
+    directives
+}
+
+/// Whether a Go source file is marked as generated, following the
+/// convention of a `// Code generated ... DO NOT EDIT.` line before the
+/// first non-comment, non-blank text
+fn is_generated_source(source_code: &str) -> bool {
+    source_code
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .take_while(|line| line.starts_with("//"))
+        .any(|line| line.starts_with("// Code generated ") && line.ends_with(" DO NOT EDIT."))
+}
+
+/// Counts every node of a syntax tree, named or anonymous, with a cursor walk
+fn count_nodes(tree: &tree_sitter::Tree) -> usize {
+    let mut cursor = tree.walk();
+    let mut count = 1;
+    loop {
+        if cursor.goto_first_child() {
+            count += 1;
+            continue;
+        }
+        loop {
+            if cursor.goto_next_sibling() {
+                count += 1;
+                break;
+            }
+            if !cursor.goto_parent() {
+                return count;
+            }
+        }
+    }
+}
+
+/// Collects the `ERROR` and `MISSING` nodes below `node`, only descending
+/// into subtrees that contain one
+fn find_syntax_errors(node: tree_sitter::Node, source_code: &str) -> Vec<SyntaxError> {
+    if !node.has_error() {
+        return Vec::new();
+    }
+
+    let message = if node.is_error() {
+        let text = node.utf8_text(source_code.as_bytes()).unwrap_or_default();
+        let text = text.lines().next().unwrap_or_default().trim();
+        Some(format!("unexpected `{}`", text))
+    } else if node.is_missing() {
+        Some(format!("missing {}", node.kind()))
+    } else {
+        None
+    };
+    if let Some(message) = message {
+        let position = node.start_position();
+        return vec![SyntaxError {
+            path: PathBuf::new(),
+            line: position.row + 1,
+            column: position.column + 1,
+            message,
+        }];
+    }
+
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .flat_map(|child| find_syntax_errors(child, source_code))
+        .collect()
+}
+
+/// Tree-sitter query extracting package metadata from a Go source file.
+///
+/// Captures `@package`, `@import`, `@grouped_import`, `@function`, `@type`
+/// and `@interface`; see `grammar_info` for the node types involved.
+const SOURCE_QUERY: &str = r#"
+(package_clause
+  (package_identifier) @package)
+
+; single imports, with either string literal form.
+; comments are siblings of import_spec and never match.
+(import_declaration
+  (import_spec
+    path: [
+      (interpreted_string_literal)
+      (raw_string_literal)
+    ]) @import)
+
+; grouped imports
+(import_spec_list
+  (import_spec
+    path: [
+      (interpreted_string_literal)
+      (raw_string_literal)
+    ]) @grouped_import)
+
+; top-level functions; methods are method_declaration nodes
+(source_file
+  (function_declaration
+    name: (identifier) @function))
+
+; top-level types, single or grouped; aliases are type_alias nodes
+(source_file
+  (type_declaration
+    (type_spec) @type))
+
+(source_file
+  (type_declaration
+    (type_spec
+      type: (interface_type)) @interface))
+
+; references to imported packages: values, calls and types
+(selector_expression
+  operand: (identifier) @qualifier)
+(qualified_type
+  package: (package_identifier) @qualifier)
+"#;
+
+/// Reads a source file, failing with `ErrorKind::InvalidData` if it isn't
+/// valid UTF-8
+fn read_source(path: &Path) -> std::io::Result<String> {
+    String::from_utf8(std::fs::read(path)?)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+}
+
+/// Reads the header of a Go source file: everything before the first
+/// top-level `func`, `type`, `var` or `const` declaration, which holds the
+/// package clause and all imports.
+///
+/// The rest of the input is only counted, reusing a single line buffer.
+///
+/// # Returns
+///
+/// * The header text and the total number of lines in the input
+fn read_import_section<R: std::io::BufRead>(mut reader: R) -> std::io::Result<(String, usize)> {
+    const DECLARATIONS: [&str; 4] = ["func", "type", "var", "const"];
+
+    let mut header = String::new();
+    let mut lines = 0;
+    let mut in_block_comment = false;
+
+    loop {
+        let start = header.len();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok((header, lines));
+        }
+        let line = &header[start..];
+
+        let keyword = line
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default();
+        if !in_block_comment && DECLARATIONS.contains(&keyword) {
+            header.truncate(start);
+            break;
+        }
+        if let Some(open) = line.rfind("/*") {
+            in_block_comment = line.rfind("*/").is_none_or(|close| close < open);
+        } else if line.contains("*/") {
+            in_block_comment = false;
+        }
+        lines += 1;
+    }
+
+    // The declaration line itself, then everything after it
+    lines += 1;
+    let mut buffer = Vec::new();
+    while reader.read_until(b'\n', &mut buffer)? > 0 {
+        lines += 1;
+        buffer.clear();
+    }
+    Ok((header, lines))
+}
+
+/// Normalizes an import path so that spellings of the same path compare
+/// equal: leading `./` segments, repeated slashes and trailing slashes are
+/// removed, e.g. `./a//b/` becomes `a/b`
+fn normalize_import_path(path: &str) -> String {
+    let mut path = path.trim();
+    while let Some(rest) = path.strip_prefix("./") {
+        path = rest;
+    }
+
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Canonicalizes a directory path so that differently spelled paths to the
+/// same directory compare equal, falling back to the path as given
+fn normalize_dir(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Escapes a string for use inside a double-quoted Graphviz ID
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escapes a string for use inside an XML attribute value
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
 }
 
+/// Errors returned by the analyzer
 #[derive(Debug)]
-enum AnalysisError {
-    IoError(std::io::Error),
-    ParseError(String),
-    TreeSitterError(String),
-    SerializationError(String),
+pub enum AnalyzeError {
+    /// Reading a file or running a tool failed
+    Io(std::io::Error),
+    /// An input file (priorities, rules, plan, ...) or source is malformed
+    Parse(String),
+    /// The Go grammar or the source query couldn't be loaded
+    TreeSitter(String),
+    /// A report couldn't be serialized or deserialized
+    Serialization(String),
+    /// `export_analysis` or `export_deployment_plan` got an unknown format
     UnsupportedFormat(String),
+    /// A glob pattern is invalid
+    InvalidPattern(String),
+    /// A `--score-formula` expression is invalid
+    InvalidFormula(String),
+    /// A numeric setting is out of range
+    InvalidRange(String),
+    /// An export option doesn't apply to the requested format
+    UnsupportedOption(String),
 }
 
-impl std::fmt::Display for AnalysisError {
+impl std::fmt::Display for AnalyzeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            AnalysisError::IoError(err) => write!(f, "I/O error: {}", err),
-            AnalysisError::ParseError(msg) => write!(f, "Parse error: {}", msg),
-            AnalysisError::TreeSitterError(msg) => write!(f, "Tree-sitter error: {}", msg),
-            AnalysisError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
-            AnalysisError::UnsupportedFormat(format) => {
+            AnalyzeError::Io(err) => write!(f, "I/O error: {}", err),
+            AnalyzeError::Parse(msg) => write!(f, "Parse error: {}", msg),
+            AnalyzeError::TreeSitter(msg) => write!(f, "Tree-sitter error: {}", msg),
+            AnalyzeError::Serialization(msg) => write!(f, "Serialization error: {}", msg),
+            AnalyzeError::UnsupportedFormat(format) => {
                 write!(f, "Unsupported output format: {}", format)
             }
+            AnalyzeError::InvalidPattern(msg) => write!(f, "Invalid pattern: {}", msg),
+            AnalyzeError::InvalidFormula(msg) => write!(f, "Invalid score formula: {}", msg),
+            AnalyzeError::InvalidRange(msg) => write!(f, "Invalid range: {}", msg),
+            AnalyzeError::UnsupportedOption(msg) => write!(f, "Unsupported option: {}", msg),
         }
     }
 }
 
-impl std::error::Error for AnalysisError {}
+impl std::error::Error for AnalyzeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AnalyzeError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
 
-impl From<std::io::Error> for AnalysisError {
+impl From<std::io::Error> for AnalyzeError {
     fn from(err: std::io::Error) -> Self {
-        AnalysisError::IoError(err)
+        AnalyzeError::Io(err)
     }
 }
 
-impl From<tree_sitter::LanguageError> for AnalysisError {
+impl From<tree_sitter::LanguageError> for AnalyzeError {
     fn from(err: tree_sitter::LanguageError) -> Self {
-        AnalysisError::TreeSitterError(err.to_string())
+        AnalyzeError::TreeSitter(err.to_string())
     }
 }
 
-impl From<tree_sitter::QueryError> for AnalysisError {
+impl From<tree_sitter::QueryError> for AnalyzeError {
     fn from(err: tree_sitter::QueryError) -> Self {
-        AnalysisError::TreeSitterError(err.to_string())
+        AnalyzeError::TreeSitter(err.to_string())
     }
 }
 
-impl From<std::str::Utf8Error> for AnalysisError {
+impl From<std::str::Utf8Error> for AnalyzeError {
     fn from(err: std::str::Utf8Error) -> Self {
-        AnalysisError::ParseError(err.to_string())
+        AnalyzeError::Parse(err.to_string())
     }
 }
 
-impl From<serde_json::Error> for AnalysisError {
+impl From<serde_json::Error> for AnalyzeError {
     fn from(err: serde_json::Error) -> Self {
-        AnalysisError::SerializationError(err.to_string())
+        AnalyzeError::Serialization(err.to_string())
+    }
+}
+
+impl From<serde_yaml::Error> for AnalyzeError {
+    fn from(err: serde_yaml::Error) -> Self {
+        AnalyzeError::Serialization(err.to_string())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fixture::TestRepo;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -480,8 +2936,7 @@ mod tests {
         assert_eq!(pkg_main.name, "main");
         assert_eq!(pkg_main.imports.len(), 2);
 
-        let expected_imports: HashSet<String> =
-            ["fmt", "os"].iter().map(|s| s.to_string()).collect();
+        let expected_imports: Set<String> = ["fmt", "os"].iter().map(|s| s.to_string()).collect();
         assert_eq!(pkg_main.imports, expected_imports);
     }
 
@@ -554,6 +3009,86 @@ mod tests {
         assert_eq!(sorted[2].name, "bar"); // 0.0
     }
 
+    #[test]
+    fn test_packages_in_range() {
+        // A -> B, C; B -> D; C -> D
+        // instability: A=1.0, B=0.5, C=0.5, D=0.0
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("A/A.go", "package A\nimport (\n\t\"B\"\n\t\"C\"\n)"),
+                ("B/B.go", "package B\nimport \"D\""),
+                ("C/C.go", "package C\nimport \"D\""),
+                ("D/D.go", "package D"),
+            ])
+            .analyze();
+        analyzer.calculate_coupling_scores();
+
+        let names = |packages: Vec<&Package>| {
+            let mut names: Vec<String> = packages.iter().map(|p| p.name.clone()).collect();
+            names.sort();
+            names
+        };
+
+        let upper = analyzer.packages_in_range(0.5, 1.0).unwrap();
+        assert_eq!(upper[0].name, "A");
+        assert_eq!(names(upper), vec!["A", "B", "C"]);
+
+        let lower = analyzer.packages_in_range(0.0, 0.5).unwrap();
+        assert_eq!(names(lower), vec!["B", "C", "D"]);
+
+        let exact = analyzer.packages_in_range(0.0, 0.0).unwrap();
+        assert_eq!(names(exact), vec!["D"]);
+
+        assert!(analyzer.packages_in_range(0.6, 0.9).unwrap().is_empty());
+        assert!(analyzer.packages_in_range(1.0, 0.0).is_err());
+        assert!(analyzer.packages_in_range(f64::NAN, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_isolated_package_is_not_reported_as_stable() {
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("app/app.go", "package app\nimport \"leaf\""),
+                ("leaf/leaf.go", "package leaf"),
+                ("alone/alone.go", "package alone"),
+            ])
+            .analyze();
+        analyzer.calculate_coupling_scores();
+
+        assert!(analyzer.packages["alone"].isolated);
+        assert!(!analyzer.packages["leaf"].isolated);
+
+        let sorted: Vec<&str> = analyzer
+            .get_sorted_packages()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(sorted, vec!["app", "leaf", "alone"]);
+
+        let stable: Vec<&str> = analyzer
+            .packages_in_range(0.0, 0.0)
+            .unwrap()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(stable, vec!["leaf"]);
+
+        let output = analyzer
+            .export_analysis("json", &Default::default())
+            .unwrap();
+        let results: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(results[1]["name"], "leaf");
+        assert_eq!(results[1]["isolated"], false);
+        assert_eq!(results[2]["name"], "alone");
+        assert_eq!(results[2]["isolated"], true);
+
+        let text = analyzer
+            .export_analysis("text", &Default::default())
+            .unwrap();
+        assert!(text.contains("Package: leaf\nCoupling Score: 0.00\n"));
+        assert!(text.contains("Package: alone\nCoupling Score: n/a (isolated)\n"));
+    }
+
     #[test]
     fn test_deployment_order() {
         // Create a simple dependency chain: A -> B -> C
@@ -584,74 +3119,321 @@ mod tests {
         assert_eq!(deployment_order[2].name, "A");
     }
 
-    /// Tests the topological sort with a more complex dependency graph
     #[test]
-    fn test_complex_dependency_graph() {
-        // Create a more complex dependency graph:
-        // A -> B, C
-        // B -> D
-        // C -> D
-        // D -> (no dependencies)
-        // E -> A, D
-        let mut file_a = NamedTempFile::new().expect("Failed to create temp file");
-        write!(file_a, "package A\nimport (\n\"B\"\n\"C\"\n)").unwrap();
+    fn test_import_paths_resolve_to_declared_package_names() {
+        let sources = [
+            ("repo/gno.land/p/demo/avl/tree.go", "package avl"),
+            // Major version directory, the package keeps its name
+            ("repo/gno.land/p/demo/tokens/v2/token.go", "package tokens"),
+            // Package clause the parser can't recover
+            (
+                "repo/gno.land/p/demo/ufmt/ufmt.go",
+                "package 42\nfunc F() {}",
+            ),
+            // Directory unrelated to the import path
+            ("vendor/fmtlib/strs.go", "package strs"),
+            (
+                "repo/gno.land/r/demo/boards/boards.go",
+                "package boards\nimport (\n\"gno.land/p/demo/avl\"\n\
+                 \"gno.land/p/demo/tokens/v2\"\n\"gno.land/p/demo/ufmt\"\n\"gno.land/p/demo/strs\"\n)",
+            ),
+        ];
+        let mut analyzer = TestRepo::new().files(&sources).analyze();
 
-        let mut file_b = NamedTempFile::new().expect("Failed to create temp file");
-        write!(file_b, "package B\nimport \"D\"").unwrap();
+        assert_eq!(analyzer.resolve_import("gno.land/p/demo/avl"), Some("avl"));
+        assert_eq!(
+            analyzer.resolve_import("gno.land/p/demo/tokens/v2"),
+            Some("tokens")
+        );
+        assert_eq!(
+            analyzer.resolve_import("gno.land/p/demo/ufmt"),
+            Some("ufmt")
+        );
+        assert_eq!(analyzer.resolve_import("gno.land/p/demo/strs"), None);
+        assert_eq!(
+            analyzer.internal_imports("boards"),
+            vec!["avl", "tokens", "ufmt"]
+        );
 
-        let mut file_c = NamedTempFile::new().expect("Failed to create temp file");
-        write!(file_c, "package C\nimport \"D\"").unwrap();
+        // boards: Ca = 0, Ce = 4; avl: Ca = 1, Ce = 0
+        analyzer.calculate_coupling_scores();
+        assert_eq!(analyzer.packages["boards"].coupling_score, 1.0);
+        assert_eq!(analyzer.packages["avl"].coupling_score, 0.0);
+        assert!(!analyzer.packages["avl"].isolated);
+    }
+
+    #[test]
+    fn test_stdlib_imports_dont_resolve_to_same_named_directories() {
+        let repo = TestRepo::new()
+            .file(
+                "app/app.go",
+                "package app\nimport (\n\"errors\"\n\"encoding/json\"\n\"example.com/proj/errs/errors\"\n)",
+            )
+            .file(
+                "util/json/j.go",
+                "package jsonutil\nimport \"encoding/json\"",
+            )
+            .file("errs/errors/e.go", "package myerrs")
+            .file("lib/lib.go", "package lib");
+        let analyzer = repo.analyze();
 
-        let mut file_d = NamedTempFile::new().expect("Failed to create temp file");
-        write!(file_d, "package D").unwrap();
+        assert_eq!(analyzer.resolve_import("errors"), None);
+        assert_eq!(analyzer.resolve_import("encoding/json"), None);
+        assert!(analyzer.find_cycles().is_empty());
+        assert_eq!(analyzer.internal_imports("app"), vec!["myerrs"]);
+        // A whole directory path still resolves below a module path
+        assert_eq!(analyzer.resolve_import("example.com/proj/lib"), Some("lib"));
+        assert_eq!(analyzer.resolve_import("util/json"), Some("jsonutil"));
+    }
 
-        let mut file_e = NamedTempFile::new().expect("Failed to create temp file");
-        write!(file_e, "package E\nimport (\n\"A\"\n\"D\"\n)").unwrap();
+    #[test]
+    fn test_abstractness_counts_interfaces_among_types() {
+        let source = "package shapes\n\
+                      type Shape interface { Area() float64 }\n\
+                      type Square struct { side float64 }\n\
+                      type Alias = Square\n\
+                      func local() { type hidden interface{} }";
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("shapes/shapes.go", source),
+                ("plain/plain.go", "package plain"),
+            ])
+            .analyze();
 
-        let mut analyzer = DependencyAnalyzer::new();
-        analyzer.analyze_file(file_a.path()).unwrap();
-        analyzer.analyze_file(file_b.path()).unwrap();
-        analyzer.analyze_file(file_c.path()).unwrap();
-        analyzer.analyze_file(file_d.path()).unwrap();
-        analyzer.analyze_file(file_e.path()).unwrap();
+        let results = analyzer.prepare_analysis_results(&analyzer.get_sorted_packages(), true);
+        let metrics = |name: &str| &results.iter().find(|r| r.name == name).unwrap().metrics;
+        assert_eq!(metrics("shapes").abstractness, 0.5);
+        assert_eq!(metrics("plain").abstractness, 0.0);
+    }
 
+    #[test]
+    fn test_distance_from_main_sequence() {
+        let sources = [
+            // Concrete and imported by everyone: I = 0, A = 0
+            ("core/core.go", "package core\ntype Value struct{}"),
+            // Only imports, all concrete: I = 1, A = 0
+            (
+                "api/api.go",
+                "package api\nimport \"core\"\ntype Handler struct{}",
+            ),
+            // Half of each: I = 1/2, A = 1/2
+            (
+                "mid/mid.go",
+                "package mid\nimport \"core\"\ntype Store interface{}\ntype store struct{}",
+            ),
+            ("app/app.go", "package app\nimport \"mid\""),
+        ];
+        let mut analyzer = TestRepo::new().files(&sources).analyze();
         analyzer.calculate_coupling_scores();
 
-        // Get deployment order
-        let deployment_order = analyzer.generate_deployment_order();
+        let results = analyzer.prepare_analysis_results(&analyzer.get_sorted_packages(), true);
+        let distance = |name: &str| {
+            results
+                .iter()
+                .find(|r| r.name == name)
+                .unwrap()
+                .metrics
+                .distance
+        };
+        assert_eq!(distance("core"), 1.0);
+        assert!(distance("api").abs() < 1e-9);
+        assert!(distance("mid").abs() < 1e-9);
 
-        // Verify topological ordering
-        assert_eq!(deployment_order.len(), 5);
+        let output = analyzer.format_text_output(&results, true);
+        assert!(output.contains("Distance from Main Sequence: 1.00\n"));
+    }
 
-        // D must come before B, C, A, and E
-        let d_pos = deployment_order.iter().position(|p| p.name == "D").unwrap();
-        let b_pos = deployment_order.iter().position(|p| p.name == "B").unwrap();
-        let c_pos = deployment_order.iter().position(|p| p.name == "C").unwrap();
-        let a_pos = deployment_order.iter().position(|p| p.name == "A").unwrap();
-        let e_pos = deployment_order.iter().position(|p| p.name == "E").unwrap();
+    #[test]
+    fn test_syntax_errors_are_located() {
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer
+            .analyze_source(Path::new("/p/ok/ok.go"), "package ok\nimport \"fmt\"", 2)
+            .unwrap();
+        assert!(analyzer.syntax_errors().is_empty());
 
-        assert!(d_pos < b_pos);
-        assert!(d_pos < c_pos);
-        assert!(b_pos < a_pos);
-        assert!(c_pos < a_pos);
-        assert!(a_pos < e_pos);
+        let broken = "package broken\n\nimport \"fmt\"\n\nfunc Run( {\n}\n";
+        analyzer
+            .analyze_source(Path::new("/p/broken/broken.go"), broken, 6)
+            .unwrap();
+        let errors = analyzer.syntax_errors();
+        assert!(!errors.is_empty());
+        assert!(
+            errors
+                .iter()
+                .all(|e| e.path == Path::new("/p/broken/broken.go"))
+        );
+        assert_eq!(errors[0].line, 5);
+
+        // Fixing the file clears its errors
+        analyzer
+            .analyze_source(Path::new("/p/broken/broken.go"), "package broken", 1)
+            .unwrap();
+        assert!(analyzer.syntax_errors().is_empty());
     }
 
-    /// Tests that the algorithm handles cyclic dependencies gracefully
     #[test]
-    fn test_cyclic_dependencies() {
-        // Create a cycle: X -> Y -> X
-        let mut file_x = NamedTempFile::new().expect("Failed to create temp file");
-        write!(file_x, "package X\nimport \"Y\"").unwrap();
+    fn test_aliased_imports() {
+        let source = "package app\n\
+                      import f \"fmt\"\n\
+                      import (\n\
+                      \tavl \"gno.land/p/demo/avl\"\n\
+                      \tstore \"gno.land/p/demo/kv\"\n\
+                      \t_ \"embed\"\n\
+                      \t. \"strings\"\n\
+                      \t\"os\"\n\
+                      )";
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("app/app.go", source),
+                ("gno.land/p/demo/kv/kv.go", "package kv"),
+            ])
+            .analyze();
 
-        let mut file_y = NamedTempFile::new().expect("Failed to create temp file");
-        write!(file_y, "package Y\nimport \"X\"").unwrap();
+        let app = &analyzer.packages["app"];
+        assert_eq!(
+            app.aliases(),
+            BTreeMap::from([
+                ("avl", "gno.land/p/demo/avl"),
+                ("f", "fmt"),
+                ("store", "gno.land/p/demo/kv"),
+            ])
+        );
+        // Imports are tracked by path whatever they are bound to
+        assert!(app.imports.contains("gno.land/p/demo/kv"));
+        assert_eq!(analyzer.internal_imports("app"), vec!["kv"]);
+    }
 
-        let mut analyzer = DependencyAnalyzer::new();
-        analyzer.analyze_file(file_x.path()).unwrap();
-        analyzer.analyze_file(file_y.path()).unwrap();
+    #[test]
+    fn test_files_of_one_package_are_merged() {
+        let repo = TestRepo::new()
+            .file("foo/first.go", "package foo\nimport \"fmt\"")
+            .file("foo/second.go", "package foo\nimport (\n\"os\"\n\"fmt\"\n)");
+        let mut analyzer = repo.analyze();
+        // Analyzing a file again doesn't duplicate its imports
+        analyzer
+            .analyze_file(&repo.path().join("foo/first.go"))
+            .unwrap();
 
-        analyzer.calculate_coupling_scores();
+        let package = &analyzer.packages["foo"];
+        let mut imports: Vec<&String> = package.imports.iter().collect();
+        imports.sort();
+        assert_eq!(imports, vec!["fmt", "os"]);
+        assert_eq!(package.files.len(), 2);
+        let paths: Vec<&str> = package
+            .import_specs
+            .iter()
+            .map(|s| s.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["fmt", "os", "fmt"]);
+    }
+
+    #[test]
+    fn test_external_weight_scales_external_imports() {
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("a/a.go", "package a\nimport (\n\"b\"\n\"fmt\"\n\"os\"\n)"),
+                ("b/b.go", "package b"),
+                ("c/c.go", "package c\nimport \"a\""),
+            ])
+            .analyze();
+
+        // a: Ca = 1, one internal and two external imports
+        for (weight, expected) in [(0.0, 1.0 / 2.0), (0.5, 2.0 / 3.0), (1.0, 3.0 / 4.0)] {
+            analyzer.set_external_weight(weight).unwrap();
+            analyzer.calculate_coupling_scores();
+            let score = analyzer.packages["a"].coupling_score;
+            assert!(
+                (score - expected).abs() < 1e-9,
+                "weight {}: {}",
+                weight,
+                score
+            );
+        }
+
+        assert!(analyzer.set_external_weight(1.5).is_err());
+        assert!(analyzer.set_external_weight(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_priorities_reorder_ready_packages() {
+        // A and B are both ready from the start; C needs both
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("a/a.go", "package A"),
+                ("b/b.go", "package B"),
+                ("c/c.go", "package C\nimport (\n\"A\"\n\"B\"\n)"),
+            ])
+            .analyze();
+        let order = |analyzer: &DependencyAnalyzer| -> Vec<String> {
+            analyzer
+                .generate_deployment_order()
+                .iter()
+                .map(|p| p.name.clone())
+                .collect()
+        };
+
+        analyzer.set_priorities(Priorities::parse("A 5\nB 1\nC 100").unwrap());
+        assert_eq!(order(&analyzer), vec!["A", "B", "C"]);
+
+        // Flipping the weights flips the tie, C still waits for both
+        analyzer.set_priorities(Priorities::parse("A 1\nB 5\nC 100").unwrap());
+        assert_eq!(order(&analyzer), vec!["B", "A", "C"]);
+    }
+
+    /// Tests the topological sort with a more complex dependency graph
+    #[test]
+    fn test_complex_dependency_graph() {
+        // Create a more complex dependency graph:
+        // A -> B, C
+        // B -> D
+        // C -> D
+        // D -> (no dependencies)
+        // E -> A, D
+        let mut analyzer = TestRepo::new()
+            .package("A", &["B", "C"])
+            .package("B", &["D"])
+            .package("C", &["D"])
+            .package("D", &[])
+            .package("E", &["A", "D"])
+            .analyze();
+
+        analyzer.calculate_coupling_scores();
+
+        // Get deployment order
+        let deployment_order = analyzer.generate_deployment_order();
+
+        // Verify topological ordering
+        assert_eq!(deployment_order.len(), 5);
+
+        // D must come before B, C, A, and E
+        let d_pos = deployment_order.iter().position(|p| p.name == "D").unwrap();
+        let b_pos = deployment_order.iter().position(|p| p.name == "B").unwrap();
+        let c_pos = deployment_order.iter().position(|p| p.name == "C").unwrap();
+        let a_pos = deployment_order.iter().position(|p| p.name == "A").unwrap();
+        let e_pos = deployment_order.iter().position(|p| p.name == "E").unwrap();
+
+        assert!(d_pos < b_pos);
+        assert!(d_pos < c_pos);
+        assert!(b_pos < a_pos);
+        assert!(c_pos < a_pos);
+        assert!(a_pos < e_pos);
+    }
+
+    /// Tests that the algorithm handles cyclic dependencies gracefully
+    #[test]
+    fn test_cyclic_dependencies() {
+        // Create a cycle: X -> Y -> X
+        let mut file_x = NamedTempFile::new().expect("Failed to create temp file");
+        write!(file_x, "package X\nimport \"Y\"").unwrap();
+
+        let mut file_y = NamedTempFile::new().expect("Failed to create temp file");
+        write!(file_y, "package Y\nimport \"X\"").unwrap();
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_file(file_x.path()).unwrap();
+        analyzer.analyze_file(file_y.path()).unwrap();
+
+        analyzer.calculate_coupling_scores();
 
         // Even with a cycle, it should return all packages
         let deployment_order = analyzer.generate_deployment_order();
@@ -663,4 +3445,1522 @@ mod tests {
         assert!(has_x);
         assert!(has_y);
     }
+
+    #[test]
+    fn test_opml_export() {
+        // A -> B -> C
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("A/A.go", "package A\nimport \"B\""),
+                ("B/B.go", "package B\nimport \"C\""),
+                ("C/C.go", "package C"),
+            ])
+            .analyze();
+        analyzer.calculate_coupling_scores();
+
+        let output = analyzer
+            .export_analysis("opml", &ExportOptions::default())
+            .unwrap();
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <head>
+    <title>Dependency tree</title>
+  </head>
+  <body>
+    <outline text="A">
+      <outline text="B">
+        <outline text="C"/>
+      </outline>
+    </outline>
+  </body>
+</opml>
+"#;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_opml_export_cycle() {
+        // X -> Y -> X, with no natural root
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("X/X.go", "package X\nimport \"Y\""),
+                ("Y/Y.go", "package Y\nimport \"X\""),
+            ])
+            .analyze();
+
+        let output = analyzer
+            .export_analysis("opml", &ExportOptions::default())
+            .unwrap();
+        assert!(output.contains(r#"<outline text="X">"#));
+        assert!(output.contains(r#"<outline text="Y">"#));
+        assert!(output.contains(r#"<outline text="X"/>"#));
+    }
+
+    #[test]
+    fn test_opml_export_expands_shared_dependencies_once() {
+        // A -> {B, C}, B -> D, C -> D, D -> E
+        let analyzer = TestRepo::new()
+            .package("A", &["B", "C"])
+            .package("B", &["D"])
+            .package("C", &["D"])
+            .package("D", &["E"])
+            .package("E", &[])
+            .analyze();
+
+        let output = analyzer
+            .export_analysis("opml", &ExportOptions::default())
+            .unwrap();
+        let expected = r#"    <outline text="A">
+      <outline text="B">
+        <outline text="D">
+          <outline text="E"/>
+        </outline>
+      </outline>
+      <outline text="C">
+        <outline text="D"/>
+      </outline>
+    </outline>
+"#;
+        assert!(output.contains(expected), "{}", output);
+        assert_eq!(output.matches(r#"<outline text="E"/>"#).count(), 1);
+    }
+
+    #[test]
+    fn test_coupling_cache_invalidation() {
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("A/A.go", "package A\nimport \"B\""),
+                ("B/B.go", "package B"),
+            ])
+            .analyze();
+        analyzer.calculate_coupling_scores();
+
+        let cached = analyzer.coupling_cache.as_ref().unwrap();
+        assert_eq!(cached["B"].afferent, 1);
+        assert_eq!(cached["A"].efferent, 1);
+
+        // A new package importing B must invalidate the cached counts
+        analyzer
+            .analyze_source(Path::new("/p/C/C.go"), "package C\nimport \"B\"", 2)
+            .unwrap();
+        assert!(analyzer.coupling_cache.is_none());
+
+        // Counts read before recalculation are computed fresh
+        assert_eq!(analyzer.coupling_counts()["B"].afferent, 2);
+
+        analyzer.calculate_coupling_scores();
+        let cached = analyzer.coupling_cache.as_ref().unwrap();
+        assert_eq!(cached["B"].afferent, 2);
+        assert_eq!(cached["C"].efferent, 1);
+    }
+
+    /// Afferent coupling as computed before the single-pass version: every
+    /// package is checked against the imports of every package
+    fn afferent_by_scanning(analyzer: &DependencyAnalyzer) -> Map<String, usize> {
+        analyzer
+            .packages
+            .keys()
+            .map(|name| {
+                if analyzer.is_discounted_import(name) {
+                    return (name.clone(), 0);
+                }
+                let afferent = analyzer
+                    .packages
+                    .values()
+                    .filter(|p| {
+                        p.imports
+                            .iter()
+                            .any(|import| analyzer.resolve_import(import) == Some(name.as_str()))
+                    })
+                    .count();
+                (name.clone(), afferent)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_single_pass_afferent_matches_scanning() {
+        let analyzer = fixture::fixture_analyzer();
+        assert_eq!(
+            analyzer.calculate_afferent_coupling(&Set::default()),
+            afferent_by_scanning(&analyzer)
+        );
+
+        // Wide import lists, self-imports, duplicate paths to one package and
+        // discounted generated packages
+        let mut analyzer = DependencyAnalyzer::new();
+        for i in 0..60 {
+            let imports: Vec<String> = (0..60)
+                .filter(|j| (i * j) % 7 < 3)
+                .map(|j| format!("\"gno.land/p/demo/p{}\"", j))
+                .chain([format!("\"p{}\"", (i + 1) % 60), "\"fmt\"".to_string()])
+                .collect();
+            let header = if i % 10 == 0 {
+                "// Code generated by mockgen. DO NOT EDIT.\n"
+            } else {
+                ""
+            };
+            let source = format!(
+                "{}package p{}\nimport (\n{}\n)",
+                header,
+                i,
+                imports.join("\n")
+            );
+            let path = format!("/wide/p{}/p{}.go", i, i);
+            analyzer
+                .analyze_source(Path::new(&path), &source, 1)
+                .unwrap();
+        }
+        analyzer.set_discount_generated_imports(true);
+        let afferent = analyzer.calculate_afferent_coupling(&Set::default());
+        assert_eq!(afferent, afferent_by_scanning(&analyzer));
+        assert_eq!(afferent["p0"], 0);
+        assert!(afferent["p1"] > 0);
+    }
+
+    #[test]
+    fn test_scores_up_to_date_tracks_streamed_files() {
+        let mut analyzer = DependencyAnalyzer::new();
+        assert!(!analyzer.scores_up_to_date());
+
+        analyzer
+            .analyze_source(Path::new("/p/A/A.go"), "package A\nimport \"B\"", 2)
+            .unwrap();
+        analyzer.calculate_coupling_scores();
+        assert!(analyzer.scores_up_to_date());
+
+        analyzer
+            .analyze_source(Path::new("/p/B/B.go"), "package B", 1)
+            .unwrap();
+        assert!(!analyzer.scores_up_to_date());
+        analyzer.calculate_coupling_scores();
+        assert!(analyzer.scores_up_to_date());
+
+        analyzer.set_external_weight(0.5).unwrap();
+        assert!(!analyzer.scores_up_to_date());
+        analyzer.calculate_coupling_scores();
+        analyzer.set_external_weight(0.5).unwrap();
+        assert!(analyzer.scores_up_to_date());
+    }
+
+    #[test]
+    fn test_hide_leaf_utilities() {
+        // util only imports the standard library and nobody imports it
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                (
+                    "util/util.go",
+                    "package util\nimport (\n\"fmt\"\n\"strings\"\n)",
+                ),
+                ("app/app.go", "package app\nimport (\n\"fmt\"\n\"lib\"\n)"),
+                ("lib/lib.go", "package lib\nimport \"fmt\""),
+            ])
+            .analyze();
+        analyzer.calculate_coupling_scores();
+
+        let output = analyzer
+            .export_analysis("text", &ExportOptions::default())
+            .unwrap();
+        assert!(output.contains("Package: util"));
+
+        let options = ExportOptions {
+            hide_leaf_utilities: true,
+            ..Default::default()
+        };
+        let output = analyzer.export_analysis("text", &options).unwrap();
+        assert!(!output.contains("Package: util"));
+        assert!(output.contains("Package: app"));
+        assert!(output.contains("Package: lib"));
+    }
+
+    #[test]
+    fn test_report_filter_pipeline() {
+        // app -> {svc, db}, svc -> db, tool -> {db, fmt}, util -> fmt
+        // instability: app=1.0, tool=1.0, util=1.0, svc=0.5, db=0.0
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                (
+                    "app/app.go",
+                    "package app\nimport (\n\t\"svc\"\n\t\"db\"\n)",
+                ),
+                ("svc/svc.go", "package svc\nimport \"db\""),
+                (
+                    "tool/tool.go",
+                    "package tool\nimport (\n\t\"db\"\n\t\"fmt\"\n)",
+                ),
+                ("util/util.go", "package util\nimport \"fmt\""),
+                ("db/db.go", "package db"),
+            ])
+            .analyze();
+        analyzer.calculate_coupling_scores();
+
+        let names = |options: &ExportOptions| {
+            let output = analyzer.export_analysis("json", options).unwrap();
+            let results: Vec<serde_json::Value> = serde_json::from_str(&output).unwrap();
+            let mut names: Vec<String> = results
+                .iter()
+                .map(|r| r["name"].as_str().unwrap().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        // Filters run before truncation: util is dropped, then two of the
+        // remaining unstable packages are kept
+        let options = ExportOptions {
+            hide_leaf_utilities: true,
+            min_score: Some(0.9),
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(names(&options), vec!["app", "tool"]);
+
+        let options = ExportOptions {
+            min_score: Some(0.5),
+            exclude_packages: vec!["t*".to_string(), "app".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(names(&options), vec!["svc", "util"]);
+
+        // Excluded packages don't take up slots: svc moves up into the top 3
+        let options = ExportOptions {
+            min_score: Some(0.5),
+            exclude_packages: vec!["util".to_string()],
+            limit: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(names(&options), vec!["app", "svc", "tool"]);
+
+        let options = ExportOptions {
+            exclude_packages: vec!["[".to_string()],
+            ..Default::default()
+        };
+        assert!(analyzer.export_analysis("json", &options).is_err());
+
+        // Zones are filtered before truncation too
+        let options = ExportOptions {
+            zone: Some(Zone::Unstable),
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(names(&options), vec!["app", "tool"]);
+        let options = ExportOptions {
+            zone: Some(Zone::Balanced),
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(names(&options), vec!["svc"]);
+
+        // min_score is checked like a packages_in_range bound
+        let options = ExportOptions {
+            min_score: Some(1.5),
+            ..Default::default()
+        };
+        assert!(analyzer.export_analysis("json", &options).is_err());
+    }
+
+    #[test]
+    fn test_parse_replace_directives() {
+        let contents = r#"
+            module gno.land/r/demo/app
+
+            replace gno.land/p/demo/foo => ./foo
+
+            replace (
+                gno.land/p/demo/bar v0.0.1 => ../bar // local checkout
+                gno.land/p/demo/baz => gno.land/p/demo/qux
+            )
+
+            replacements gno.land/p/demo/x => ./x
+            replace(
+                gno.land/p/demo/y => ./y
+            )
+        "#;
+
+        let directives = parse_replace_directives(contents);
+        assert_eq!(
+            directives,
+            vec![
+                ("gno.land/p/demo/foo".to_string(), "./foo".to_string()),
+                ("gno.land/p/demo/bar".to_string(), "../bar".to_string()),
+                (
+                    "gno.land/p/demo/baz".to_string(),
+                    "gno.land/p/demo/qux".to_string()
+                ),
+                ("gno.land/p/demo/y".to_string(), "./y".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_longest_replace_directive_wins() {
+        let repo = TestRepo::new()
+            .file(
+                "gno.mod",
+                "replace a/b => ./outer\nreplace a/b/c => ./inner\nreplace a/b/c/d => ./deep\n",
+            )
+            .file("outer/outer.go", "package outer")
+            .file("outer/c/shadow.go", "package shadow")
+            .file("inner/inner.go", "package inner")
+            .file("deep/deep.go", "package deep");
+        let analyzer = repo.analyze();
+
+        assert_eq!(analyzer.resolve_import("a/b"), Some("outer"));
+        assert_eq!(analyzer.resolve_import("a/b/c"), Some("inner"));
+        assert_eq!(analyzer.resolve_import("a/b/c/d"), Some("deep"));
+    }
+
+    #[test]
+    fn test_gno_mod_replace_resolves_import() {
+        let root = tempfile::tempdir().expect("Failed to create temp dir");
+        std::fs::create_dir(root.path().join("impl")).unwrap();
+
+        std::fs::write(
+            root.path().join("gno.mod"),
+            "module gno.land/r/demo/app\n\nreplace gno.land/p/demo/foo => ./impl\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.path().join("app.go"),
+            "package app\nimport \"gno.land/p/demo/foo\"",
+        )
+        .unwrap();
+        std::fs::write(root.path().join("impl").join("impl.go"), "package impl").unwrap();
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_file(&root.path().join("app.go")).unwrap();
+        analyzer
+            .analyze_file(&root.path().join("impl").join("impl.go"))
+            .unwrap();
+
+        // Without the replacement the import matches neither a directory nor
+        // a package name and is external
+        assert_eq!(analyzer.resolve_import("gno.land/p/demo/foo"), None);
+
+        analyzer
+            .analyze_gno_mod(&root.path().join("gno.mod"))
+            .unwrap();
+        analyzer.calculate_coupling_scores();
+
+        assert_eq!(analyzer.resolve_import("gno.land/p/demo/foo"), Some("impl"));
+        assert_eq!(analyzer.coupling_counts()["impl"].afferent, 1);
+
+        let deployment_order = analyzer.generate_deployment_order();
+        assert_eq!(deployment_order[0].name, "impl");
+        assert_eq!(deployment_order[1].name, "app");
+    }
+
+    #[test]
+    fn test_exclude_patterns_skip_files_and_directories() {
+        let repo = TestRepo::new()
+            .package("app", &["lib"])
+            .package("lib", &[])
+            .file("app/app_test.go", "package apptest\nimport \"app\"")
+            .file("vendor/dep/dep.go", "package dep");
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer
+            .set_exclude_patterns(&["*_test.go".to_string(), "vendor".to_string()])
+            .unwrap();
+        analyzer.analyze_directory(repo.path()).unwrap();
+
+        let mut names: Vec<&str> = analyzer.packages.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, vec!["app", "lib"]);
+
+        assert!(analyzer.set_exclude_patterns(&["[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_deployignore_skips_listed_paths() {
+        let repo = TestRepo::new()
+            .package("app", &["lib"])
+            .package("lib", &[])
+            .file("examples/demo/demo.go", "package demo\nimport \"app\"")
+            .file("gen/gen.go", "package gen")
+            .file(".deployignore", "# not deployed\n\n/examples/\ngen\n");
+        let analyzer = repo.analyze();
+
+        let mut names: Vec<&str> = analyzer.packages.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, vec!["app", "lib"]);
+
+        std::fs::write(repo.path().join(".deployignore"), "ok\n[\n").unwrap();
+        let mut analyzer = DependencyAnalyzer::new();
+        assert!(matches!(
+            analyzer.analyze_directory(repo.path()),
+            Err(AnalyzeError::InvalidPattern(message)) if message.starts_with(".deployignore line 2")
+        ));
+    }
+
+    #[test]
+    fn test_coupling_scores_match_calculated_scores() {
+        let sources = [
+            ("app/app.go", "package app\nimport (\n\"lib\"\n\"fmt\"\n)"),
+            ("lib/lib.go", "package lib\nimport \"os\""),
+            ("tools/tools.go", "package tools"),
+        ];
+        let mut analyzer = TestRepo::new().files(&sources).analyze();
+        analyzer.set_external_weight(0.5).unwrap();
+
+        let scores = analyzer.coupling_scores();
+        assert!(!analyzer.scores_up_to_date());
+        assert!(analyzer.packages.values().all(|p| p.coupling_score == 0.0));
+
+        analyzer.calculate_coupling_scores();
+        assert_eq!(scores.len(), analyzer.packages.len());
+        for package in analyzer.packages.values() {
+            assert_eq!(
+                scores[&package.name], package.coupling_score,
+                "{}",
+                package.name
+            );
+        }
+        assert_eq!(analyzer.coupling_scores(), scores);
+    }
+
+    #[test]
+    fn test_build_tags_exclude_unsatisfied_files() {
+        let repo = TestRepo::new()
+            .package("app", &["lib"])
+            .package("lib", &[])
+            .package("mock", &[])
+            .file(
+                "app/mock.go",
+                "//go:build integration\n\npackage app\nimport \"mock\"",
+            );
+        let imports = |analyzer: &DependencyAnalyzer| {
+            let mut imports: Vec<String> =
+                analyzer.packages["app"].imports.iter().cloned().collect();
+            imports.sort();
+            imports
+        };
+
+        // Without tags, constraints are ignored
+        assert_eq!(imports(&repo.analyze()), vec!["lib", "mock"]);
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.set_build_tags(Some(&[]));
+        analyzer.analyze_directory(repo.path()).unwrap();
+        assert_eq!(imports(&analyzer), vec!["lib"]);
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.set_build_tags(Some(&["integration".to_string()]));
+        analyzer.analyze_directory(repo.path()).unwrap();
+        assert_eq!(imports(&analyzer), vec!["lib", "mock"]);
+    }
+
+    #[test]
+    fn test_gno_mod_module_path_identifies_packages() {
+        let repo = TestRepo::new()
+            .file("gno.mod", "module gno.land/r/demo // demo realms\n")
+            .file("a/main.go", "package main\nimport \"gno.land/r/demo/lib\"")
+            .file("b/main.go", "package main")
+            .file("lib/lib.go", "package lib");
+        let analyzer = repo.analyze();
+
+        let mut names: Vec<&str> = analyzer.packages.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "gno.land/r/demo/a",
+                "gno.land/r/demo/b",
+                "gno.land/r/demo/lib"
+            ]
+        );
+        // Both main packages keep their declared name
+        for key in ["gno.land/r/demo/a", "gno.land/r/demo/b"] {
+            assert_eq!(analyzer.packages[key].name(), "main");
+            assert_eq!(analyzer.packages[key].import_path(), key);
+        }
+        assert_eq!(analyzer.packages["gno.land/r/demo/lib"].name(), "lib");
+        let report = analyzer
+            .export_analysis("text", &Default::default())
+            .unwrap();
+        assert!(report.contains("Package: main\n"));
+        assert!(report.contains("Import Path: gno.land/r/demo/b\n"));
+        assert_eq!(
+            analyzer.internal_imports("gno.land/r/demo/a"),
+            vec!["gno.land/r/demo/lib"]
+        );
+
+        assert_eq!(
+            parse_module_directive("// x\nmodule \"gno.land/p/x\"\n"),
+            Some("gno.land/p/x".to_string())
+        );
+        assert_eq!(parse_module_directive("modules x\n"), None);
+    }
+
+    #[test]
+    fn test_restrict_to_prefixes() {
+        let root = tempfile::tempdir().expect("Failed to create temp dir");
+        for (dir, source) in [
+            (
+                "foo",
+                "package foo\nimport (\n\"gno.land/r/demo/bar\"\n\"gno.land/p/util\"\n)",
+            ),
+            ("bar", "package bar"),
+            ("util", "package util"),
+            ("demos", "package demos"),
+        ] {
+            std::fs::create_dir(root.path().join(dir)).unwrap();
+            std::fs::write(root.path().join(dir).join("pkg.go"), source).unwrap();
+        }
+        std::fs::write(
+            root.path().join("gno.mod"),
+            "replace (\n\tgno.land/r/demo/foo => ./foo\n\tgno.land/r/demo/bar => ./bar\n\tgno.land/p/util => ./util\n\tgno.land/r/demos => ./demos\n)\n",
+        )
+        .unwrap();
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_directory(root.path()).unwrap();
+        // foo is only imported by its own name here, so give it a dependent
+        // through its import path
+        analyzer
+            .analyze_source(
+                &root.path().join("demos").join("pkg.go"),
+                "package demos\nimport \"gno.land/r/demo/foo\"",
+                2,
+            )
+            .unwrap();
+        assert_eq!(analyzer.resolve_import("gno.land/p/util"), Some("util"));
+
+        analyzer.restrict_to_prefixes(&["gno.land/r/demo/".to_string()]);
+        analyzer.calculate_coupling_scores();
+
+        let mut names: Vec<&str> = analyzer.packages.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, vec!["bar", "foo"]);
+
+        // foo -> bar stays internal, foo -> util is now external
+        assert_eq!(analyzer.internal_imports("foo"), vec!["bar"]);
+        assert_eq!(analyzer.resolve_import("gno.land/p/util"), None);
+        let counts = analyzer.coupling_counts();
+        assert_eq!(counts["foo"].efferent, 2);
+        assert_eq!(counts["foo"].afferent, 0);
+        assert_eq!(counts["bar"].afferent, 1);
+    }
+
+    #[test]
+    fn test_list_packages() {
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("avl/avl.go", "package avl\nimport \"ufmt\""),
+                ("ufmt/ufmt.go", "package ufmt"),
+                (
+                    "avlhelper/avlhelper.go",
+                    "package avlhelper\nimport \"avl\"",
+                ),
+            ])
+            .analyze();
+
+        let output = analyzer.list_packages(None, false).unwrap();
+        assert_eq!(output, "avl\navlhelper\nufmt\n");
+
+        let output = analyzer.list_packages(Some("avl*"), false).unwrap();
+        assert_eq!(output, "avl\navlhelper\n");
+
+        let output = analyzer.list_packages(Some("avl"), true).unwrap();
+        assert_eq!(output, "avl\n  - ufmt\n");
+
+        assert!(analyzer.list_packages(Some("[avl"), false).is_err());
+    }
+
+    #[test]
+    fn test_import_block_with_comments() {
+        let mut file = NamedTempFile::new().expect("Failed to create temp file");
+        let go_source = r#"
+            package main
+
+            import (
+                // "commented/out"
+                "fmt" // trailing comment
+
+                /* block comment */ "os"
+                // the next spec has a preceding comment
+                "gno.land/p/demo/avl"
+                "strings"; "errors"
+                `raw/path`
+            ) // after the block
+        "#;
+        write!(file, "{}", go_source).unwrap();
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_file(file.path()).unwrap();
+
+        let expected: Set<String> = [
+            "fmt",
+            "os",
+            "gno.land/p/demo/avl",
+            "strings",
+            "errors",
+            "raw/path",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(analyzer.packages["main"].imports, expected);
+    }
+
+    #[test]
+    fn test_mixed_import_declarations_capture_each_spec_once() {
+        let go_source = r#"
+            package main
+
+            import "fmt" // single, with a trailing comment
+
+            import (
+                "os" // trailing comment
+                log "github.com/x/log" /* aliased */
+                // "commented/out"
+                _ "driver" // blank
+            )
+
+            import "strings"
+            import (
+                "errors"
+            )
+        "#;
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer
+            .analyze_source(Path::new("/p/main/main.go"), go_source, 1)
+            .unwrap();
+
+        let specs: Vec<(&str, ImportForm)> = analyzer.packages["main"]
+            .import_specs
+            .iter()
+            .map(|spec| (spec.path.as_str(), spec.form))
+            .collect();
+        assert_eq!(
+            specs,
+            vec![
+                ("fmt", ImportForm::Single),
+                ("os", ImportForm::Grouped),
+                ("github.com/x/log", ImportForm::Grouped),
+                ("driver", ImportForm::Grouped),
+                ("strings", ImportForm::Single),
+                ("errors", ImportForm::Grouped),
+            ]
+        );
+        assert_eq!(analyzer.packages["main"].imports.len(), 6);
+    }
+
+    #[test]
+    fn test_deployment_waves() {
+        // A -> B, C; B -> D; C -> D
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("A/A.go", "package A\nimport (\n\"B\"\n\"C\"\n)"),
+                ("B/B.go", "package B\nimport \"D\""),
+                ("C/C.go", "package C\nimport \"D\""),
+                ("D/D.go", "package D"),
+            ])
+            .analyze();
+
+        let waves: Vec<Vec<&str>> = analyzer
+            .deployment_waves()
+            .iter()
+            .map(|wave| wave.iter().map(|p| p.name.as_str()).collect())
+            .collect();
+        assert_eq!(waves, vec![vec!["D"], vec!["B", "C"], vec!["A"]]);
+    }
+
+    #[test]
+    fn test_gha_matrix_export() {
+        // A -> B -> C
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("A/A.go", "package A\nimport \"B\""),
+                ("B/B.go", "package B\nimport \"C\""),
+                ("C/C.go", "package C"),
+            ])
+            .analyze();
+
+        let output = analyzer.export_deployment_plan("gha-matrix").unwrap();
+        let matrix: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(matrix, serde_json::json!({ "wave": [["C"], ["B"], ["A"]] }));
+
+        let output = analyzer.export_deployment_plan("json").unwrap();
+        let order: Vec<String> = serde_json::from_str(&output).unwrap();
+        assert_eq!(order, ["C", "B", "A"]);
+        assert_eq!(analyzer.export_deployment_plan("text").unwrap(), "C\nB\nA");
+
+        assert!(analyzer.export_deployment_plan("unknown").is_err());
+    }
+
+    #[test]
+    fn test_import_forms() {
+        let analyzer = TestRepo::new()
+            .files(&[(
+                "main/main.go",
+                "package main\nimport \"fmt\"\nimport (\n\"os\"\n\"strings\"\n)",
+            )])
+            .analyze();
+
+        let forms: Vec<(&str, ImportForm)> = analyzer.packages["main"]
+            .import_specs
+            .iter()
+            .map(|spec| (spec.path.as_str(), spec.form))
+            .collect();
+        assert_eq!(
+            forms,
+            vec![
+                ("fmt", ImportForm::Single),
+                ("os", ImportForm::Grouped),
+                ("strings", ImportForm::Grouped),
+            ]
+        );
+
+        let detailed = ExportOptions {
+            detailed: true,
+            ..Default::default()
+        };
+        let output = analyzer.export_analysis("json", &detailed).unwrap();
+        let results: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            results[0]["import_specs"][0],
+            serde_json::json!({ "path": "fmt", "form": "single" })
+        );
+        assert_eq!(results[0]["import_specs"][1]["form"], "grouped");
+
+        let output = analyzer
+            .export_analysis("json", &ExportOptions::default())
+            .unwrap();
+        assert!(!output.contains("import_specs"));
+    }
+
+    #[test]
+    fn test_parallel_parsing_is_deterministic() {
+        // 40 packages of 5 files each, every file importing something else
+        let mut repo = TestRepo::new();
+        for package in 0..40 {
+            for file in 0..5 {
+                repo = repo.file(
+                    &format!("p{0}/f{1}.go", package, file),
+                    &format!(
+                        "package p{}\nimport (\n\"p{}\"\n\"ext{}\"\n)\nfunc F{}() {{}}",
+                        package,
+                        (package + file + 1) % 40,
+                        file,
+                        file
+                    ),
+                );
+            }
+        }
+
+        let report = |threads: usize| {
+            let mut analyzer = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| repo.analyze());
+            analyzer.calculate_coupling_scores();
+            let detailed = ExportOptions {
+                detailed: true,
+                ..Default::default()
+            };
+            let files: Vec<Vec<PathBuf>> = (0..40)
+                .map(|i| analyzer.packages[&format!("p{}", i)].files.clone())
+                .collect();
+            (analyzer.export_analysis("json", &detailed).unwrap(), files)
+        };
+
+        let serial = report(1);
+        assert_eq!(serial.1[0].len(), 5);
+        assert_eq!(report(8), serial);
+    }
+
+    #[test]
+    fn test_unresolved_imports() {
+        let analyzer = TestRepo::new()
+            .package("app", &["fmt", "lib", "github.com/org/x"])
+            .package("lib", &[])
+            .analyze();
+
+        let unresolved = analyzer.unresolved_imports();
+        assert_eq!(
+            unresolved.get("app"),
+            Some(&vec!["fmt".to_string(), "github.com/org/x".to_string()])
+        );
+        assert!(!unresolved.contains_key("lib"));
+    }
+
+    #[test]
+    fn test_package_import_path_and_name() {
+        let analyzer = TestRepo::new()
+            .file("cmd/server/main.go", "package main\nimport \"lib\"")
+            .package("lib", &[])
+            .analyze();
+
+        let server = &analyzer.packages["main"];
+        assert_eq!(server.name(), "main");
+        assert_eq!(server.import_path(), "cmd/server");
+        assert_eq!(analyzer.packages["lib"].import_path(), "lib");
+
+        let output = analyzer
+            .export_analysis("json", &ExportOptions::default())
+            .unwrap();
+        let results: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let main = results
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|result| result["name"] == "main")
+            .unwrap();
+        assert_eq!(main["import_path"], "cmd/server");
+
+        let text = analyzer
+            .export_analysis("text", &ExportOptions::default())
+            .unwrap();
+        assert!(text.contains("Package: main\n"), "{}", text);
+        assert!(text.contains("Import Path: cmd/server\n"), "{}", text);
+        assert!(!text.contains("Import Path: lib\n"), "{}", text);
+    }
+
+    #[test]
+    fn test_blank_and_dot_imports() {
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                (
+                    "app/app.go",
+                    "package app\nimport (\n_ \"driver\"\n. \"strings\"\n\"lib\"\n)",
+                ),
+                ("lib/lib.go", "package lib"),
+                ("driver/driver.go", "package driver"),
+                ("main/main.go", "package main\nimport \"app\""),
+            ])
+            .analyze();
+
+        let kinds: Vec<(&str, ImportKind)> = analyzer.packages["app"]
+            .import_specs
+            .iter()
+            .map(|spec| (spec.path.as_str(), spec.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("driver", ImportKind::Blank),
+                ("strings", ImportKind::Dot),
+                ("lib", ImportKind::Normal),
+            ]
+        );
+
+        // app: Ca = 1, Ce = 3
+        analyzer.calculate_coupling_scores();
+        assert!((analyzer.packages["app"].coupling_score - 0.75).abs() < 1e-9);
+
+        // The blank import of driver no longer counts: Ce = 2
+        analyzer.set_exclude_blank_imports(true);
+        assert!(!analyzer.scores_up_to_date());
+        analyzer.calculate_coupling_scores();
+        assert!((analyzer.packages["app"].coupling_score - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(analyzer.coupling_counts()["driver"].afferent, 1);
+    }
+
+    #[test]
+    fn test_dot_deploy_export() {
+        // A -> B, C; B -> D; C -> D
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("A/A.go", "package A\nimport (\n\"B\"\n\"C\"\n)"),
+                ("B/B.go", "package B\nimport \"D\""),
+                ("C/C.go", "package C\nimport \"D\""),
+                ("D/D.go", "package D"),
+            ])
+            .analyze();
+
+        let output = analyzer.export_deployment_plan("dot-deploy").unwrap();
+        let expected = r#"digraph deployment {
+  { rank=same; "D"; }
+  { rank=same; "B"; "C"; }
+  { rank=same; "A"; }
+  "B" -> "A";
+  "C" -> "A";
+  "D" -> "B";
+  "D" -> "C";
+}
+"#;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_exported_functions() {
+        let realm_source = r#"
+            package realm
+
+            type Counter struct{}
+
+            func Increment() {}
+            func Render(path string) string { return "" }
+            func helper() {}
+            func (c *Counter) Exported() {}
+        "#;
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("tiny/tiny.go", "package tiny\nfunc Only() {}"),
+                ("realm/realm.go", realm_source),
+            ])
+            .analyze();
+        assert_eq!(analyzer.packages["realm"].exported_functions, 2);
+        assert_eq!(analyzer.packages["tiny"].exported_functions, 1);
+
+        let options = ExportOptions {
+            sort_by: SortKey::ApiSurface,
+            ..Default::default()
+        };
+        let output = analyzer.export_analysis("json", &options).unwrap();
+        let results: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(results[0]["name"], "realm");
+        assert_eq!(results[0]["metrics"]["exported_functions"], 2);
+        assert_eq!(results[1]["name"], "tiny");
+    }
+
+    #[test]
+    fn test_unreadable_files_are_skipped() {
+        let root = tempfile::tempdir().expect("Failed to create temp dir");
+        std::fs::write(root.path().join("good.go"), "package good").unwrap();
+
+        // A file that disappears mid-walk looks like a dangling symlink
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.path().join("gone.go"), root.path().join("link.go"))
+            .unwrap();
+
+        // Permissions are not enforced everywhere (e.g. for root)
+        #[cfg(unix)]
+        let locked_is_unreadable = {
+            use std::os::unix::fs::PermissionsExt;
+            let locked = root.path().join("locked.go");
+            std::fs::write(&locked, "package locked").unwrap();
+            std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+            std::fs::read_to_string(&locked).is_err()
+        };
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_directory(root.path()).unwrap();
+        assert!(analyzer.packages.contains_key("good"));
+
+        #[cfg(unix)]
+        {
+            let skipped = analyzer.skipped_files();
+            assert!(
+                skipped
+                    .iter()
+                    .any(|s| s.path.ends_with("link.go") && s.kind == std::io::ErrorKind::NotFound)
+            );
+
+            if locked_is_unreadable {
+                assert!(skipped.iter().any(|s| s.path.ends_with("locked.go")
+                    && s.kind == std::io::ErrorKind::PermissionDenied));
+                assert!(!analyzer.packages.contains_key("locked"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_non_utf8_files_are_skipped() {
+        let root = tempfile::tempdir().expect("Failed to create temp dir");
+        std::fs::write(root.path().join("good.go"), "package good").unwrap();
+        let binary = root.path().join("binary.go");
+        std::fs::write(&binary, b"package bad\n// \xff\xfe\x00").unwrap();
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_directory(root.path()).unwrap();
+        assert!(analyzer.packages.contains_key("good"));
+        assert!(!analyzer.packages.contains_key("bad"));
+
+        let skipped = analyzer.skipped_files();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].path, binary);
+        assert_eq!(skipped[0].kind, std::io::ErrorKind::InvalidData);
+        assert!(
+            skipped[0]
+                .to_string()
+                .ends_with("binary.go: not valid UTF-8")
+        );
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_file(&binary).unwrap();
+        assert!(analyzer.packages.is_empty());
+        assert_eq!(analyzer.skipped_files().len(), 1);
+    }
+
+    #[test]
+    fn test_index_maps_package_to_files() {
+        let root = tempfile::tempdir().expect("Failed to create temp dir");
+        std::fs::write(root.path().join("a.go"), "package foo\nimport \"fmt\"").unwrap();
+        std::fs::write(root.path().join("b.go"), "package foo\nimport \"os\"").unwrap();
+        std::fs::write(root.path().join("bar.go"), "package bar").unwrap();
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_directory(root.path()).unwrap();
+
+        let index = analyzer.index();
+        assert_eq!(
+            index["foo"],
+            vec![root.path().join("a.go"), root.path().join("b.go")]
+        );
+        assert_eq!(index["bar"], vec![root.path().join("bar.go")]);
+    }
+
+    #[test]
+    fn test_normalize_import_path() {
+        assert_eq!(normalize_import_path("a/b"), "a/b");
+        assert_eq!(normalize_import_path("a/b/"), "a/b");
+        assert_eq!(normalize_import_path("./a/b"), "a/b");
+        assert_eq!(normalize_import_path("a//b"), "a/b");
+        assert_eq!(
+            normalize_import_path("gno.land/p/demo/avl"),
+            "gno.land/p/demo/avl"
+        );
+    }
+
+    #[test]
+    fn test_equivalent_import_paths_are_deduplicated() {
+        let mut file = NamedTempFile::new().expect("Failed to create temp file");
+        write!(
+            file,
+            "package main\nimport (\n\"a/b\"\n\"a/b/\"\n\"./a/b\"\n)"
+        )
+        .unwrap();
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_file(file.path()).unwrap();
+        analyzer.calculate_coupling_scores();
+
+        assert_eq!(analyzer.coupling_counts()["main"].efferent, 1);
+        assert!(analyzer.packages["main"].imports.contains("a/b"));
+    }
+
+    #[test]
+    fn test_analyze_file_imports_stops_at_declarations() {
+        let body = "// filler\n".repeat(10_000);
+        let source = format!(
+            "package big\n\n/*\nfunc inComment() {{}}\n*/\nimport (\n\t\"fmt\"\n\t\"strings\"\n)\n\nfunc Exported() {{\n{}}}\n",
+            body
+        );
+        let mut file = NamedTempFile::new().expect("Failed to create temp file");
+        write!(file, "{}", source).unwrap();
+
+        let (header, lines) =
+            read_import_section(std::io::BufReader::new(source.as_bytes())).unwrap();
+        assert!(header.ends_with(")\n\n"));
+        assert!(!header.contains("Exported"));
+        assert_eq!(lines, source.lines().count());
+
+        let mut streamed = DependencyAnalyzer::new();
+        streamed.analyze_file_imports(file.path()).unwrap();
+        let mut full = DependencyAnalyzer::new();
+        full.analyze_file(file.path()).unwrap();
+
+        let streamed = &streamed.packages["big"];
+        let full = &full.packages["big"];
+        assert_eq!(streamed.imports, full.imports);
+        assert_eq!(streamed.lines_of_code, full.lines_of_code);
+        assert_eq!(streamed.exported_functions, 0);
+        assert_eq!(full.exported_functions, 1);
+    }
+
+    #[test]
+    fn test_packages_do_not_retain_source() {
+        let filler = "x".repeat(100_000);
+        let mut file = NamedTempFile::new().expect("Failed to create temp file");
+        write!(
+            file,
+            "package big\nimport \"fmt\"\n// {}\nfunc Run() {{}}\n",
+            filler
+        )
+        .unwrap();
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_file(file.path()).unwrap();
+
+        // Every field is metadata; none of them hold on to the source text
+        let retained = format!("{:?}", analyzer);
+        assert!(!retained.contains(&filler[..64]));
+        assert!(retained.len() < 2048);
+    }
+
+    #[test]
+    fn test_ast_nodes_grow_with_source_size() {
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer
+            .analyze_source(Path::new("/tiny/tiny.go"), "package tiny", 1)
+            .unwrap();
+        let large = "package large\n\nimport \"fmt\"\n\nfunc Run(n int) int {\n\tif n > 1 {\n\t\tfmt.Println(n)\n\t\treturn Run(n - 1) * n\n\t}\n\treturn 1\n}\n";
+        analyzer
+            .analyze_source(Path::new("/large/a.go"), large, 11)
+            .unwrap();
+
+        let tiny = analyzer.packages["tiny"].ast_nodes();
+        let single = analyzer.packages["large"].ast_nodes();
+        // source_file, package_clause, `package` and the identifier
+        assert_eq!(tiny, 4);
+        assert!(single > tiny * 5, "{} vs {}", single, tiny);
+
+        // A second file of the same package adds to the total, while
+        // re-analyzing a known file replaces its count
+        analyzer
+            .analyze_source(Path::new("/large/b.go"), "package large", 1)
+            .unwrap();
+        assert_eq!(analyzer.packages["large"].ast_nodes(), single + 4);
+        analyzer
+            .analyze_source(Path::new("/large/b.go"), "package large", 1)
+            .unwrap();
+        assert_eq!(analyzer.packages["large"].ast_nodes(), single + 4);
+    }
+
+    #[test]
+    fn test_discount_generated_imports() {
+        let mut analyzer = TestRepo::new().files(&[
+            (
+                "api/api.go",
+                "package api\nimport (\n\t\"apipb\"\n\t\"db\"\n)",
+            ),
+            ("db/db.go", "package db"),
+            (
+                "apipb/api.pb.go",
+                "// Code generated by protoc-gen-go. DO NOT EDIT.\n// source: api.proto\n\npackage apipb",
+            ),
+            (
+                "mixed/gen.go",
+                "// Code generated by mockgen. DO NOT EDIT.\npackage mixed",
+            ),
+            ("mixed/hand.go", "package mixed\nimport \"db\""),
+        ]).analyze();
+
+        assert!(analyzer.packages["apipb"].is_generated());
+        assert!(!analyzer.packages["mixed"].is_generated());
+        assert_eq!(
+            analyzer.generated_imports(&analyzer.packages["api"]),
+            vec!["apipb"]
+        );
+
+        analyzer.calculate_coupling_scores();
+        assert_eq!(analyzer.coupling_counts()["api"].efferent, 2);
+        assert_eq!(analyzer.coupling_counts()["apipb"].afferent, 1);
+
+        analyzer.set_discount_generated_imports(true);
+        analyzer.calculate_coupling_scores();
+        let counts = analyzer.coupling_counts();
+        assert_eq!(counts["api"].efferent, 1);
+        assert_eq!(counts["apipb"].afferent, 0);
+        assert_eq!(counts["db"].afferent, 2);
+
+        let options = ExportOptions {
+            detailed: true,
+            ..Default::default()
+        };
+        let output = analyzer.export_analysis("text", &options).unwrap();
+        assert!(output.contains("Generated Imports: apipb\n"));
+    }
+
+    #[test]
+    fn test_fx_maps_match_std_reference() {
+        // Synthetic acyclic graph: package pN imports a few lower-numbered packages
+        let root = tempfile::tempdir().expect("Failed to create temp dir");
+        let mut reference: HashMap<String, Vec<String>> = HashMap::new();
+        for i in 0..60usize {
+            let imports: Vec<String> = (1..=3)
+                .filter(|k| i >= *k)
+                .map(|k| format!("p{}", (i * 7 + k * 13) % i.max(1)))
+                .collect();
+            let source = format!(
+                "package p{}\nimport (\n{}\n\"fmt\"\n)",
+                i,
+                imports
+                    .iter()
+                    .map(|import| format!("\"{}\"", import))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+            std::fs::write(root.path().join(format!("p{}.go", i)), source).unwrap();
+
+            let mut all_imports = imports;
+            all_imports.push("fmt".to_string());
+            all_imports.sort();
+            all_imports.dedup();
+            reference.insert(format!("p{}", i), all_imports);
+        }
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_directory(root.path()).unwrap();
+        analyzer.calculate_coupling_scores();
+
+        // Recompute instability with std collections
+        for (name, imports) in &reference {
+            let afferent = reference
+                .values()
+                .filter(|other| other.contains(name))
+                .count() as f64;
+            let efferent = imports.len() as f64;
+            let expected = efferent / (afferent + efferent);
+            assert!(
+                (analyzer.packages[name].coupling_score - expected).abs() < f64::EPSILON,
+                "{}",
+                name
+            );
+        }
+
+        let order: Vec<&str> = analyzer
+            .generate_deployment_order()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        for (name, imports) in &reference {
+            let position = order.iter().position(|p| p == name).unwrap();
+            for import in imports.iter().filter(|import| *import != "fmt") {
+                assert!(order.iter().position(|p| p == import).unwrap() < position);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported_format_error() {
+        let analyzer = DependencyAnalyzer::new();
+        assert!(matches!(
+            analyzer.export_analysis("nope", &Default::default()),
+            Err(AnalyzeError::UnsupportedFormat(format)) if format == "nope"
+        ));
+        assert!(matches!(
+            analyzer.export_deployment_plan("nope"),
+            Err(AnalyzeError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_graph_formats_reject_package_filters() {
+        let analyzer = DependencyAnalyzer::new();
+        let options = ExportOptions {
+            zone: Some(Zone::Unstable),
+            limit: Some(1),
+            ..Default::default()
+        };
+        for format in GRAPH_FORMATS {
+            match analyzer.export_analysis(format, &options) {
+                Err(AnalyzeError::UnsupportedOption(msg)) => {
+                    assert!(msg.ends_with("can't apply zone, limit"), "{}", msg)
+                }
+                other => panic!("{}: expected an error, got {:?}", format, other),
+            }
+            assert!(
+                analyzer
+                    .export_analysis(
+                        format,
+                        &ExportOptions {
+                            legend: true,
+                            ..Default::default()
+                        }
+                    )
+                    .is_ok()
+            );
+        }
+        assert!(analyzer.export_analysis("csv", &options).is_ok());
+    }
+
+    #[test]
+    fn test_analyze_reader() {
+        let mut analyzer = DependencyAnalyzer::new();
+        let source = "package app\n\nimport (\n\t\"fmt\"\n\t\"lib\"\n)\n";
+        analyzer
+            .analyze_reader(std::io::Cursor::new(source), "<stdin>")
+            .unwrap();
+
+        let package = &analyzer.packages["app"];
+        assert_eq!(package.lines_of_code, 6);
+        assert_eq!(package.files, [PathBuf::from("<stdin>")]);
+        let mut imports: Vec<&str> = package.imports().collect();
+        imports.sort();
+        assert_eq!(imports, ["fmt", "lib"]);
+
+        let invalid = std::io::Cursor::new(vec![0xff, 0xfe]);
+        assert!(matches!(
+            analyzer.analyze_reader(invalid, "<stdin>"),
+            Err(AnalyzeError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_yaml_matches_json_output() {
+        let sources = [
+            ("app/app.go", "package app\nimport \"lib\""),
+            ("lib/lib.go", "package lib"),
+        ];
+        let mut analyzer = TestRepo::new().files(&sources).analyze();
+        analyzer.calculate_coupling_scores();
+
+        let options = ExportOptions {
+            detailed: true,
+            ..Default::default()
+        };
+        let yaml = analyzer.export_analysis("yaml", &options).unwrap();
+        let json = analyzer.export_analysis("json", &options).unwrap();
+
+        let from_yaml: serde_json::Value = serde_yaml::from_str(&yaml).unwrap();
+        let from_json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(from_yaml, from_json);
+
+        let names: Vec<&str> = from_yaml
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|package| package["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["app", "lib"]);
+    }
+
+    #[test]
+    fn test_deployment_order_breaks_ties_by_name() {
+        // Independent roots z, m and a; y and b both wait for z
+        let sources = [
+            ("z/z.go", "package z"),
+            ("m/m.go", "package m"),
+            ("a/a.go", "package a"),
+            ("y/y.go", "package y\nimport \"z\""),
+            ("b/b.go", "package b\nimport \"z\""),
+        ];
+        let order = |sources: &[(&str, &str)]| {
+            let analyzer = TestRepo::new().files(sources).analyze();
+            let names: Vec<String> = analyzer
+                .generate_deployment_order()
+                .iter()
+                .map(|package| package.name.clone())
+                .collect();
+            names
+        };
+
+        let first = order(&sources);
+        assert_eq!(first, ["a", "m", "z", "b", "y"]);
+        assert_eq!(order(&sources), first);
+        let mut reversed = sources;
+        reversed.reverse();
+        assert_eq!(order(&reversed), first);
+    }
+
+    #[test]
+    fn test_parse_cache_skips_unchanged_files() {
+        let repo = TestRepo::new().package("app", &["lib"]).package("lib", &[]);
+        let analyze = || {
+            let mut analyzer = DependencyAnalyzer::new();
+            analyzer.set_parse_cache(true);
+            analyzer.analyze_directory(repo.path()).unwrap();
+            analyzer
+        };
+
+        assert_eq!(analyze().cache_hits(), 0);
+        assert!(repo.path().join(CACHE_DIR).is_dir());
+
+        let analyzer = analyze();
+        assert_eq!(analyzer.cache_hits(), 2);
+        assert!(analyzer.packages["app"].imports.contains("lib"));
+
+        // A changed file is parsed again
+        std::fs::write(
+            repo.path().join("app/app.go"),
+            "package app\nimport \"fmt\"",
+        )
+        .unwrap();
+        let analyzer = analyze();
+        assert_eq!(analyzer.cache_hits(), 1);
+        assert!(analyzer.packages["app"].imports.contains("fmt"));
+        assert_eq!(analyze().cache_hits(), 2);
+
+        // A cache from another crate, extractor or query version is ignored
+        let cache_file = repo.path().join(CACHE_DIR).join("parse.json");
+        let mut cache: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&cache_file).unwrap()).unwrap();
+        assert_eq!(cache["version"], cache::cache_version());
+        cache["version"] = env!("CARGO_PKG_VERSION").into();
+        std::fs::write(&cache_file, serde_json::to_vec(&cache).unwrap()).unwrap();
+        assert_eq!(analyze().cache_hits(), 0);
+        assert_eq!(analyze().cache_hits(), 2);
+
+        // Disabled by default
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_directory(repo.path()).unwrap();
+        assert_eq!(analyzer.cache_hits(), 0);
+    }
+
+    #[test]
+    fn test_remove_and_reanalyze_file() {
+        let sources = [
+            ("a/a.go", "package a\nimport \"fmt\"\nfunc A() {}"),
+            (
+                "a/extra.go",
+                "package a\nimport \"lib\"\nfunc B() {}\nfunc C() {}",
+            ),
+            ("lib/lib.go", "package lib"),
+        ];
+        let repo = TestRepo::new().files(&sources);
+        let mut analyzer = repo.analyze();
+        let fresh = repo.analyze();
+        assert_eq!(analyzer.packages["a"].lines_of_code, 7);
+        assert_eq!(analyzer.packages["a"].exported_functions, 3);
+
+        let extra = repo.path().join("a/extra.go");
+        assert!(analyzer.remove_file(&extra));
+        assert!(!analyzer.remove_file(&extra));
+        let a = &analyzer.packages["a"];
+        assert_eq!(a.imports().collect::<Vec<_>>(), ["fmt"]);
+        assert_eq!((a.lines_of_code, a.exported_functions), (3, 1));
+        assert_eq!(analyzer.impact_counts()["lib"], 0);
+
+        // Adding the file back restores the original state
+        analyzer.analyze_file(&extra).unwrap();
+        assert_eq!(analyzer.packages["a"], fresh.packages["a"]);
+
+        // A file switching packages leaves the old one
+        analyzer
+            .analyze_source(&extra, "package other\nimport \"lib\"", 2)
+            .unwrap();
+        assert!(!analyzer.packages["a"].imports.contains("lib"));
+        assert_eq!(analyzer.packages["other"].files, [extra]);
+
+        // Removing a package's last file removes the package
+        assert!(analyzer.remove_file(&repo.path().join("lib/lib.go")));
+        assert!(!analyzer.packages.contains_key("lib"));
+        assert_eq!(analyzer.resolve_import("lib"), None);
+    }
+
+    #[test]
+    fn test_weighted_efferent_counts_references() {
+        let sources = [
+            (
+                "light/light.go",
+                "package light\nimport \"strings\"\nfunc F() { strings.TrimSpace(\"\") }",
+            ),
+            (
+                "heavy/heavy.go",
+                "package heavy\nimport (\n\"strings\"\ny \"gopkg.in/yaml.v3\"\n\"example.com/mod/v2\"\n_ \"embed\"\n)\n\
+                 var s strings.Builder\n\
+                 func F() {\n\
+                 \tstrings.TrimSpace(strings.ToLower(\"\"))\n\
+                 \ty.Marshal(nil)\n\
+                 \tmod.Run()\n\
+                 \tembed.FS()\n\
+                 }",
+            ),
+        ];
+        let analyzer = TestRepo::new().files(&sources).analyze();
+
+        assert_eq!(analyzer.packages["light"].weighted_efferent(), 1);
+        // 3 strings (one a type), 1 y, 1 mod; the blank import binds no name
+        assert_eq!(analyzer.packages["heavy"].weighted_efferent(), 5);
+
+        let output = analyzer
+            .export_analysis("json", &Default::default())
+            .unwrap();
+        let results: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let weight = |name: &str| {
+            results
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|result| result["name"] == name)
+                .unwrap()["metrics"]["weighted_efferent"]
+                .clone()
+        };
+        assert_eq!(weight("light"), 1);
+        assert_eq!(weight("heavy"), 5);
+    }
 }
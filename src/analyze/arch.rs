@@ -0,0 +1,142 @@
+use std::fmt;
+use std::path::Path;
+
+use super::{AnalyzeError, DependencyAnalyzer};
+
+/// Allowed dependencies between packages, read from an architecture file.
+///
+/// Each non-blank line holds one rule `FROM -> TO`, where both sides are
+/// glob patterns over package names; `#` starts a comment. An import is
+/// permitted if at least one rule matches it, everything else is forbidden.
+///
+/// ```text
+/// # services may use any repository
+/// *service -> *repository
+/// * -> util
+/// ```
+#[derive(Debug, Default)]
+pub struct ArchRules {
+    rules: Vec<(glob::Pattern, glob::Pattern)>,
+}
+
+/// An import of an analyzed package that no architecture rule allows
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ArchViolation {
+    /// The importing package
+    pub from: String,
+    /// The imported package
+    pub to: String,
+}
+
+impl fmt::Display for ArchViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} imports {}, which no rule allows", self.from, self.to)
+    }
+}
+
+impl ArchRules {
+    /// Reads rules from an architecture file
+    pub fn load(path: &Path) -> Result<Self, AnalyzeError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses rules from the contents of an architecture file
+    pub fn parse(contents: &str) -> Result<Self, AnalyzeError> {
+        let mut rules = Vec::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (from, to) = line.split_once("->").ok_or_else(|| {
+                AnalyzeError::Parse(format!(
+                    "line {}: expected `FROM -> TO`, found `{}`",
+                    number + 1,
+                    line
+                ))
+            })?;
+            let pattern = |glob: &str| {
+                glob::Pattern::new(glob.trim()).map_err(|e| {
+                    AnalyzeError::InvalidPattern(format!("line {}: {}", number + 1, e))
+                })
+            };
+            rules.push((pattern(from)?, pattern(to)?));
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Whether some rule allows `from` to import `to`
+    pub fn allows(&self, from: &str, to: &str) -> bool {
+        self.rules
+            .iter()
+            .any(|(source, target)| source.matches(from) && target.matches(to))
+    }
+}
+
+impl DependencyAnalyzer {
+    /// Finds imports between analyzed packages that the rules don't allow.
+    ///
+    /// Imports of packages outside the analyzed project aren't checked.
+    ///
+    /// # Returns
+    ///
+    /// * Violations sorted by importing, then imported package
+    pub fn arch_violations(&self, rules: &ArchRules) -> Vec<ArchViolation> {
+        let mut violations: Vec<ArchViolation> = self
+            .packages
+            .keys()
+            .flat_map(|name| {
+                self.internal_imports(name)
+                    .into_iter()
+                    .filter(|dependency| !rules.allows(name, dependency))
+                    .map(|dependency| ArchViolation {
+                        from: name.clone(),
+                        to: dependency.to_string(),
+                    })
+            })
+            .collect();
+
+        violations.sort();
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_only_forbidden_edges_are_reported() {
+        let analyzer = TestRepo::new()
+            .files(&[
+                (
+                    "userservice/userservice.go",
+                    "package userservice\nimport (\n\"userrepository\"\n\"fmt\"\n)",
+                ),
+                (
+                    "userrepository/userrepository.go",
+                    "package userrepository\nimport \"userservice\"",
+                ),
+            ])
+            .analyze();
+
+        let rules = ArchRules::parse("# layering\n*service -> *repository\n\n").unwrap();
+        assert_eq!(
+            analyzer.arch_violations(&rules),
+            vec![ArchViolation {
+                from: "userrepository".to_string(),
+                to: "userservice".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_malformed_rules_are_rejected() {
+        assert!(ArchRules::parse("service repository").is_err());
+        assert!(ArchRules::parse("[ -> repository").is_err());
+    }
+}
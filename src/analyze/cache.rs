@@ -0,0 +1,90 @@
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+use super::{Map, SOURCE_QUERY, SourceInfo};
+
+/// Directory below an analyzed root holding the parse cache
+pub const CACHE_DIR: &str = ".deploy-cache";
+
+/// File in `CACHE_DIR` holding the cached sources
+const CACHE_FILE: &str = "parse.json";
+
+/// Version of what is extracted from a source file; bump it whenever
+/// `SourceInfo` or its extraction changes in a way `SOURCE_QUERY` doesn't
+/// show
+const EXTRACTOR_VERSION: u32 = 1;
+
+/// Information extracted from the source files of one root, so unchanged
+/// files don't have to be parsed again.
+///
+/// Entries are keyed by path relative to the root and only used while the
+/// file's content hash matches. A cache written by another version of the
+/// crate or the extractor, or with another source query, is ignored, see
+/// `cache_version`.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub(super) struct ParseCache {
+    version: String,
+    files: Map<PathBuf, CachedSource>,
+}
+
+/// What a source file yielded, with the hash of the content it came from
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct CachedSource {
+    hash: u64,
+    lines: usize,
+    info: SourceInfo,
+}
+
+impl ParseCache {
+    /// Reads the cache of `root`; a missing, unreadable or outdated cache
+    /// yields an empty one
+    pub(super) fn load(root: &Path) -> Self {
+        std::fs::read(root.join(CACHE_DIR).join(CACHE_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_slice::<Self>(&contents).ok())
+            .filter(|cache| cache.version == cache_version())
+            .unwrap_or_default()
+    }
+
+    /// Returns the cached information and line count of `relative`, if
+    /// the file still has the content hashing to `hash`
+    pub(super) fn get(&self, relative: &Path, hash: u64) -> Option<(SourceInfo, usize)> {
+        self.files
+            .get(relative)
+            .filter(|cached| cached.hash == hash)
+            .map(|cached| (cached.info.clone(), cached.lines))
+    }
+
+    /// Records what `relative` yielded for the content hashing to `hash`
+    pub(super) fn insert(&mut self, relative: PathBuf, hash: u64, info: SourceInfo, lines: usize) {
+        self.files
+            .insert(relative, CachedSource { hash, lines, info });
+    }
+
+    /// Writes the cache below `root`, creating `CACHE_DIR` if needed
+    pub(super) fn save(mut self, root: &Path) -> std::io::Result<()> {
+        self.version = cache_version();
+        let dir = root.join(CACHE_DIR);
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(CACHE_FILE), serde_json::to_vec(&self)?)
+    }
+}
+
+/// Identifies how cached sources were extracted: the crate version, the
+/// extractor version and a hash of the source query
+pub(super) fn cache_version() -> String {
+    format!(
+        "{}+{}.{:016x}",
+        env!("CARGO_PKG_VERSION"),
+        EXTRACTOR_VERSION,
+        content_hash(SOURCE_QUERY)
+    )
+}
+
+/// Hashes file contents; FxHash has no per-process seed, so hashes are
+/// stable across runs
+pub(super) fn content_hash(contents: &str) -> u64 {
+    let mut hasher = rustc_hash::FxHasher::default();
+    hasher.write(contents.as_bytes());
+    hasher.finish()
+}
@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::{AnalyzeError, DependencyAnalyzer, Map, Set};
+
+/// Marks the start of a commit in the `git log` output parsed by
+/// `record_git_log`
+const COMMIT_MARKER: char = '@';
+
+impl DependencyAnalyzer {
+    /// Counts, for every package, the commits touching one of its files.
+    ///
+    /// Runs `git log` in the repository containing `path`; merge commits are
+    /// left out. The counts feed `bus_factor_risk`.
+    ///
+    /// Can be called once per analyzed root: packages of another repository
+    /// keep their counts, and loading the same repository again changes
+    /// nothing.
+    ///
+    /// # Returns
+    ///
+    /// * `Err` if `git` can't be run or `path` isn't inside a git repository
+    pub fn load_git_churn(&mut self, path: &Path) -> Result<(), AnalyzeError> {
+        let git = |args: &[&str]| -> Result<String, AnalyzeError> {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(path)
+                .args(args)
+                .output()?;
+            if !output.status.success() {
+                return Err(AnalyzeError::Io(std::io::Error::other(format!(
+                    "git {} failed: {}",
+                    args.join(" "),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))));
+            }
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        };
+
+        let root = git(&["rev-parse", "--show-toplevel"])?;
+        let log = git(&[
+            "log",
+            "--no-merges",
+            &format!("--format={}%H", COMMIT_MARKER),
+            "--name-only",
+        ])?;
+        self.record_git_log(Path::new(root.trim()), &log);
+        Ok(())
+    }
+
+    /// Records churn from `git log --format=@%H --name-only` output, whose
+    /// file paths are relative to the repository `root`
+    pub(super) fn record_git_log(&mut self, root: &Path, log: &str) {
+        let mut packages_by_file: Map<PathBuf, &str> = Map::default();
+        for package in self.packages.values() {
+            for file in &package.files {
+                packages_by_file.insert(canonical_file(file), &package.key);
+            }
+        }
+
+        let mut churn: Map<String, usize> = Map::default();
+        let mut touched: Set<&str> = Set::default();
+        for line in log.lines().chain([COMMIT_MARKER.to_string().as_str()]) {
+            if line.starts_with(COMMIT_MARKER) {
+                for name in touched.drain() {
+                    *churn.entry(name.to_string()).or_default() += 1;
+                }
+            } else if !line.is_empty()
+                && let Some(name) = packages_by_file.get(&canonical_file(&root.join(line)))
+            {
+                touched.insert(name);
+            }
+        }
+
+        let merged = self.churn.get_or_insert_default();
+        for (name, commits) in churn {
+            let count = merged.entry(name).or_default();
+            *count = (*count).max(commits);
+        }
+    }
+
+    /// Number of commits touching the package, 0 before `load_git_churn`
+    pub(super) fn churn(&self, name: &str) -> usize {
+        self.churn
+            .as_ref()
+            .and_then(|churn| churn.get(name))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// How much losing the package's main author would hurt: its churn
+    /// times its afferent coupling, so only packages that change often
+    /// *and* are widely depended upon score high
+    pub(super) fn bus_factor_risk(&self, name: &str, afferent: usize) -> f64 {
+        (self.churn(name) * afferent) as f64
+    }
+}
+
+/// Canonicalizes a file path so git's paths and walked paths compare equal,
+/// falling back to the path as given
+fn canonical_file(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyze::fixture::TestRepo;
+    use crate::analyze::{ExportOptions, SortKey};
+
+    #[test]
+    fn test_churned_core_package_has_highest_bus_risk() {
+        let sources = [
+            ("core/core.go", "package core"),
+            ("legacy/legacy.go", "package legacy"),
+            ("a/a.go", "package a\nimport (\n\"core\"\n\"legacy\"\n)"),
+            ("b/b.go", "package b\nimport (\n\"core\"\n\"legacy\"\n)"),
+        ];
+        let repo = TestRepo::new().files(&sources);
+        let mut analyzer = repo.analyze();
+        analyzer.calculate_coupling_scores();
+
+        let log = "@c4\ncore/core.go\na/a.go\n\n@c3\ncore/core.go\n\n@c2\ncore/core.go\n\
+                   legacy/legacy.go\n\n@c1\nREADME.md\n";
+        analyzer.record_git_log(repo.path(), log);
+        assert_eq!(analyzer.churn("core"), 3);
+        assert_eq!(analyzer.churn("legacy"), 1);
+        assert_eq!(analyzer.churn("b"), 0);
+
+        let options = ExportOptions {
+            sort_by: SortKey::BusRisk,
+            ..Default::default()
+        };
+        let output = analyzer.export_analysis("json", &options).unwrap();
+        let results: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(results[0]["name"], "core");
+        assert_eq!(results[0]["metrics"]["bus_factor_risk"], 6.0);
+        assert_eq!(results[1]["name"], "legacy");
+        assert_eq!(results[1]["metrics"]["churn"], 1);
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+
+use super::DependencyAnalyzer;
+
+/// Two packages coupled through the imports they share
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct CoDependency {
+    /// The package whose name sorts first
+    pub first: String,
+    /// The package whose name sorts second
+    pub second: String,
+    /// Imports both packages have in common, sorted
+    pub shared: Vec<String>,
+}
+
+impl fmt::Display for CoDependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} <-> {}: {} shared import(s) ({})",
+            self.first,
+            self.second,
+            self.shared.len(),
+            self.shared.join(", ")
+        )
+    }
+}
+
+impl DependencyAnalyzer {
+    /// Finds logical coupling between packages.
+    ///
+    /// Two packages importing the same packages tend to change together even
+    /// when neither imports the other, a coupling the import graph alone
+    /// doesn't show. Every pair of packages sharing at least one import is
+    /// reported, internal and external imports alike.
+    ///
+    /// # Returns
+    ///
+    /// * Pairs sorted by number of shared imports, most co-dependent first,
+    ///   then by package names
+    pub fn co_dependencies(&self) -> Vec<CoDependency> {
+        let mut names: Vec<&String> = self.packages.keys().collect();
+        names.sort();
+
+        let mut pairs = Vec::new();
+        for (index, first) in names.iter().enumerate() {
+            let first_imports = &self.packages[*first].imports;
+            for second in &names[index + 1..] {
+                let mut shared: Vec<String> = self.packages[*second]
+                    .imports
+                    .iter()
+                    .filter(|import| first_imports.contains(*import))
+                    .cloned()
+                    .collect();
+                if shared.is_empty() {
+                    continue;
+                }
+                shared.sort();
+                pairs.push(CoDependency {
+                    first: first.to_string(),
+                    second: second.to_string(),
+                    shared,
+                });
+            }
+        }
+
+        pairs.sort_by(|a, b| {
+            b.shared
+                .len()
+                .cmp(&a.shared.len())
+                .then_with(|| (&a.first, &a.second).cmp(&(&b.first, &b.second)))
+        });
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_pairs_sharing_more_imports_rank_first() {
+        let analyzer = TestRepo::new()
+            .files(&[
+                (
+                    "a/a.go",
+                    "package a\nimport (\n\"fmt\"\n\"strings\"\n\"db\"\n)",
+                ),
+                (
+                    "b/b.go",
+                    "package b\nimport (\n\"fmt\"\n\"strings\"\n\"db\"\n)",
+                ),
+                ("c/c.go", "package c\nimport \"fmt\""),
+                ("d/d.go", "package d\nimport \"os\""),
+            ])
+            .analyze();
+
+        let pairs = analyzer.co_dependencies();
+        assert_eq!(pairs[0].first, "a");
+        assert_eq!(pairs[0].second, "b");
+        assert_eq!(pairs[0].shared, vec!["db", "fmt", "strings"]);
+        assert_eq!(
+            pairs[0].to_string(),
+            "a <-> b: 3 shared import(s) (db, fmt, strings)"
+        );
+
+        let rest: Vec<(&str, &str, usize)> = pairs[1..]
+            .iter()
+            .map(|p| (p.first.as_str(), p.second.as_str(), p.shared.len()))
+            .collect();
+        assert_eq!(rest, vec![("a", "c", 1), ("b", "c", 1)]);
+    }
+}
@@ -0,0 +1,167 @@
+use super::Set;
+
+/// A `//go:build` expression such as `linux && (amd64 || arm64)`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub(super) enum BuildConstraint {
+    Tag(String),
+    Not(Box<BuildConstraint>),
+    And(Box<BuildConstraint>, Box<BuildConstraint>),
+    Or(Box<BuildConstraint>, Box<BuildConstraint>),
+}
+
+impl BuildConstraint {
+    /// Reads the `//go:build` line of a source file.
+    ///
+    /// Like the Go toolchain, only comments before the package clause are
+    /// considered. Legacy `// +build` lines are ignored.
+    ///
+    /// # Returns
+    ///
+    /// * `None` if the file has no constraint or it can't be parsed
+    pub(super) fn from_source(source_code: &str) -> Option<Self> {
+        source_code
+            .lines()
+            .map(str::trim)
+            .take_while(|line| !line.starts_with("package"))
+            .find_map(|line| line.strip_prefix("//go:build "))
+            .and_then(Self::parse)
+    }
+
+    /// Parses a constraint expression, `None` if it is malformed
+    pub(super) fn parse(expression: &str) -> Option<Self> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+        let constraint = parser.or()?;
+        (parser.position == tokens.len()).then_some(constraint)
+    }
+
+    /// Whether a build with exactly `tags` set includes the file
+    pub(super) fn is_satisfied(&self, tags: &Set<String>) -> bool {
+        match self {
+            BuildConstraint::Tag(tag) => tags.contains(tag),
+            BuildConstraint::Not(inner) => !inner.is_satisfied(tags),
+            BuildConstraint::And(left, right) => {
+                left.is_satisfied(tags) && right.is_satisfied(tags)
+            }
+            BuildConstraint::Or(left, right) => left.is_satisfied(tags) || right.is_satisfied(tags),
+        }
+    }
+}
+
+/// Splits an expression into tags and the operators `!`, `&&`, `||`, `(`
+/// and `)`
+fn tokenize(expression: &str) -> Option<Vec<&str>> {
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_start();
+    while !rest.is_empty() {
+        let length = if rest.starts_with("&&") || rest.starts_with("||") {
+            2
+        } else if rest.starts_with(['!', '(', ')']) {
+            1
+        } else {
+            let length = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            if length == 0 {
+                return None;
+            }
+            length
+        };
+        tokens.push(&rest[..length]);
+        rest = rest[length..].trim_start();
+    }
+    Some(tokens)
+}
+
+/// Recursive descent over the tokens of a constraint, `||` binding looser
+/// than `&&`, which binds looser than `!`
+struct Parser<'a> {
+    tokens: &'a [&'a str],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn next_is(&self, token: &str) -> bool {
+        self.tokens.get(self.position) == Some(&token)
+    }
+
+    fn or(&mut self) -> Option<BuildConstraint> {
+        let mut left = self.and()?;
+        while self.next_is("||") {
+            self.position += 1;
+            left = BuildConstraint::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Some(left)
+    }
+
+    fn and(&mut self) -> Option<BuildConstraint> {
+        let mut left = self.not()?;
+        while self.next_is("&&") {
+            self.position += 1;
+            left = BuildConstraint::And(Box::new(left), Box::new(self.not()?));
+        }
+        Some(left)
+    }
+
+    fn not(&mut self) -> Option<BuildConstraint> {
+        if self.next_is("!") {
+            self.position += 1;
+            return Some(BuildConstraint::Not(Box::new(self.not()?)));
+        }
+        if self.next_is("(") {
+            self.position += 1;
+            let inner = self.or()?;
+            if !self.next_is(")") {
+                return None;
+            }
+            self.position += 1;
+            return Some(inner);
+        }
+
+        let token = *self.tokens.get(self.position)?;
+        if ["&&", "||", ")"].contains(&token) {
+            return None;
+        }
+        self.position += 1;
+        Some(BuildConstraint::Tag(token.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_evaluate_constraints() {
+        let tags: Set<String> = ["linux", "arm64"].map(String::from).into_iter().collect();
+        let satisfied = |expression| {
+            BuildConstraint::parse(expression)
+                .unwrap()
+                .is_satisfied(&tags)
+        };
+
+        assert!(satisfied("linux"));
+        assert!(!satisfied("!linux"));
+        assert!(satisfied("linux && (amd64 || arm64)"));
+        assert!(!satisfied("linux && !arm64"));
+        assert!(satisfied("windows || linux && arm64"));
+        assert!(!satisfied("(windows || linux) && amd64"));
+
+        for malformed in ["", "linux &&", "(linux", "linux arm64", "a & b"] {
+            assert_eq!(BuildConstraint::parse(malformed), None, "{}", malformed);
+        }
+
+        let source = "// Copyright\n\n//go:build integration\n\npackage app\n//go:build other\n";
+        assert_eq!(
+            BuildConstraint::from_source(source),
+            Some(BuildConstraint::Tag("integration".to_string()))
+        );
+        assert_eq!(
+            BuildConstraint::from_source("package app\n//go:build x\n"),
+            None
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use super::{DependencyAnalyzer, PackageAnalysis};
+
+/// Column names of the CSV report, in order
+const HEADER: [&str; 7] = [
+    "name",
+    "instability",
+    "afferent",
+    "efferent",
+    "abstractness",
+    "distance",
+    "import_count",
+];
+
+impl DependencyAnalyzer {
+    /// Formats the reported packages as CSV, one row per package in report
+    /// order, for loading into a spreadsheet or dataframe.
+    ///
+    /// Fields containing commas, quotes or line breaks are quoted.
+    pub(super) fn format_csv_output(&self, results: &[PackageAnalysis]) -> String {
+        let mut output = HEADER.join(",");
+        output.push('\n');
+
+        for result in results {
+            let metrics = &result.metrics;
+            let row = [
+                csv_field(&result.name),
+                metrics.instability.to_string(),
+                metrics.afferent_coupling.to_string(),
+                metrics.efferent_coupling.to_string(),
+                metrics.abstractness.to_string(),
+                metrics.distance.to_string(),
+                result.imports.len().to_string(),
+            ];
+            output.push_str(&row.join(","));
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_csv_header_and_rows() {
+        let sources = [
+            ("app/app.go", "package app\nimport (\n\"lib\"\n\"fmt\"\n)"),
+            ("lib/lib.go", "package lib"),
+        ];
+        let mut analyzer = TestRepo::new().files(&sources).analyze();
+        analyzer.calculate_coupling_scores();
+
+        let output = analyzer
+            .export_analysis("csv", &Default::default())
+            .unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            "name,instability,afferent,efferent,abstractness,distance,import_count"
+        );
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "app,1,0,2,0,0,2");
+        assert_eq!(lines[2], "lib,0,1,0,0,1,0");
+
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
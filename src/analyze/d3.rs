@@ -0,0 +1,88 @@
+use super::{DependencyAnalyzer, Zone};
+
+impl DependencyAnalyzer {
+    /// Formats the import graph as JSON for D3.js force-directed layouts.
+    ///
+    /// Produces `{"nodes": [...], "links": [...]}` as expected by
+    /// `d3.forceSimulation` and `d3.forceLink`: every package becomes a node
+    /// with its name as `id`, its instability zone as `group` and its
+    /// `instability`; every internal import becomes a link whose `value` is
+    /// the number of import specs pointing at the dependency, i.e. how many
+    /// of the importer's files import it. Nodes and links are sorted by name.
+    pub(super) fn format_d3_output(&self) -> Result<String, serde_json::Error> {
+        let mut names: Vec<&str> = self.packages.keys().map(String::as_str).collect();
+        names.sort();
+
+        let nodes: Vec<serde_json::Value> = names
+            .iter()
+            .map(|name| {
+                let package = &self.packages[*name];
+                serde_json::json!({
+                    "id": name,
+                    "group": Zone::of(package.isolated, package.coupling_score).label(),
+                    "instability": package.coupling_score,
+                })
+            })
+            .collect();
+
+        let mut links = Vec::new();
+        for name in &names {
+            let specs = &self.packages[*name].import_specs;
+            for dependency in self.internal_imports(name) {
+                let value = specs
+                    .iter()
+                    .filter(|spec| self.resolve_import(&spec.path) == Some(dependency))
+                    .count();
+                links.push(serde_json::json!({
+                    "source": name,
+                    "target": dependency,
+                    "value": value,
+                }));
+            }
+        }
+
+        serde_json::to_string_pretty(&serde_json::json!({
+            "nodes": nodes,
+            "links": links,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_nodes_and_links_have_d3_keys() {
+        let sources = [
+            ("app/main.go", "package app\nimport (\n\"lib\"\n\"fmt\"\n)"),
+            ("app/util.go", "package app\nimport \"lib\""),
+            ("lib/lib.go", "package lib\nimport \"util\""),
+            ("util/util.go", "package util"),
+        ];
+        let mut analyzer = TestRepo::new().files(&sources).analyze();
+        analyzer.calculate_coupling_scores();
+
+        let output = analyzer.export_analysis("d3", &Default::default()).unwrap();
+        let graph: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+        let nodes = graph["nodes"].as_array().unwrap();
+        assert_eq!(nodes.len(), 3);
+        for node in nodes {
+            let mut keys: Vec<&String> = node.as_object().unwrap().keys().collect();
+            keys.sort();
+            assert_eq!(keys, vec!["group", "id", "instability"]);
+        }
+        assert_eq!(nodes[0]["id"], "app");
+        assert_eq!(nodes[0]["group"], "Unstable");
+        assert_eq!(nodes[2]["group"], "Stable");
+
+        let links = graph["links"].as_array().unwrap();
+        assert_eq!(links.len(), 2);
+        assert_eq!(
+            links[0],
+            serde_json::json!({"source": "app", "target": "lib", "value": 2})
+        );
+        assert_eq!(links[1]["value"], 1);
+    }
+}
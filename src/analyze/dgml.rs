@@ -0,0 +1,101 @@
+use super::{DependencyAnalyzer, Zone, escape_xml};
+
+/// Instability zones used to style DGML nodes, with their ARGB background
+const ZONES: [(&str, &str); 4] = [
+    ("Stable", "#FF4CAF50"),
+    ("Balanced", "#FFFFC107"),
+    ("Unstable", "#FFF44336"),
+    ("Isolated", "#FF9E9E9E"),
+];
+
+impl DependencyAnalyzer {
+    /// Formats the import graph as DGML for Visual Studio's graph viewer.
+    ///
+    /// Every analyzed package becomes a `<Node>` categorized by its
+    /// instability zone, see `Zone`. Each internal import becomes a
+    /// `<Link>`. Nodes and links are sorted by name.
+    pub(super) fn format_dgml_output(&self) -> String {
+        let mut names: Vec<&str> = self.packages.keys().map(String::as_str).collect();
+        names.sort();
+
+        let mut output = String::new();
+        output.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        output.push_str("<DirectedGraph xmlns=\"http://schemas.microsoft.com/vs/2009/dgml\">\n");
+
+        output.push_str("  <Nodes>\n");
+        for name in &names {
+            let package = &self.packages[*name];
+            let zone = Zone::of(package.isolated, package.coupling_score).label();
+            output.push_str(&format!(
+                "    <Node Id=\"{}\" Label=\"{}\" Category=\"{}\" Instability=\"{:.2}\" />\n",
+                escape_xml(name),
+                escape_xml(name),
+                zone,
+                package.coupling_score
+            ));
+        }
+        output.push_str("  </Nodes>\n");
+
+        output.push_str("  <Links>\n");
+        for name in &names {
+            for dependency in self.internal_imports(name) {
+                output.push_str(&format!(
+                    "    <Link Source=\"{}\" Target=\"{}\" />\n",
+                    escape_xml(name),
+                    escape_xml(dependency)
+                ));
+            }
+        }
+        output.push_str("  </Links>\n");
+
+        output.push_str("  <Categories>\n");
+        for (zone, background) in ZONES {
+            output.push_str(&format!(
+                "    <Category Id=\"{}\" Background=\"{}\" />\n",
+                zone, background
+            ));
+        }
+        output.push_str("  </Categories>\n");
+
+        output.push_str("  <Properties>\n");
+        output.push_str(
+            "    <Property Id=\"Instability\" Label=\"Instability\" DataType=\"System.Double\" />\n",
+        );
+        output.push_str("  </Properties>\n");
+        output.push_str("</DirectedGraph>\n");
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_dgml_export() {
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("app/app.go", "package app\nimport (\n\"lib\"\n\"fmt\"\n)"),
+                ("lib/lib.go", "package lib"),
+                ("x/x.go", "package x"),
+            ])
+            .analyze();
+        analyzer.calculate_coupling_scores();
+
+        let output = analyzer
+            .export_analysis("dgml", &Default::default())
+            .unwrap();
+        assert!(
+            output.contains("<DirectedGraph xmlns=\"http://schemas.microsoft.com/vs/2009/dgml\">")
+        );
+        assert!(output.trim_end().ends_with("</DirectedGraph>"));
+        assert_eq!(output.matches("<Node ").count(), 3);
+        assert!(output.contains(
+            "<Node Id=\"app\" Label=\"app\" Category=\"Unstable\" Instability=\"1.00\" />"
+        ));
+        assert!(output.contains("<Node Id=\"lib\" Label=\"lib\" Category=\"Stable\""));
+        assert!(output.contains("<Node Id=\"x\" Label=\"x\" Category=\"Isolated\""));
+        assert_eq!(output.matches("<Link ").count(), 1);
+        assert!(output.contains("<Link Source=\"app\" Target=\"lib\" />"));
+    }
+}
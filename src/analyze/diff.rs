@@ -0,0 +1,214 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use super::{AnalyzeError, DependencyAnalyzer};
+
+/// An import of one analyzed package by another
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+pub struct ImportEdge {
+    /// The importing package
+    pub from: String,
+    /// The imported package
+    pub to: String,
+}
+
+/// A package whose instability differs between two analyses
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ScoreChange {
+    /// The package, analyzed in both versions
+    pub package: String,
+    /// Instability in the old analysis
+    pub before: f64,
+    /// Instability in the new analysis
+    pub after: f64,
+}
+
+/// How the architecture changed between two analyses of a project, e.g.
+/// before and after a pull request
+#[derive(Debug, Clone, PartialEq, Default, serde::Serialize)]
+pub struct AnalysisDiff {
+    /// Packages only in the new analysis, sorted by name
+    pub added_packages: Vec<String>,
+    /// Packages only in the old analysis, sorted by name
+    pub removed_packages: Vec<String>,
+    /// Imports between analyzed packages only in the new analysis
+    pub added_edges: Vec<ImportEdge>,
+    /// Imports between analyzed packages only in the old analysis
+    pub removed_edges: Vec<ImportEdge>,
+    /// Packages in both analyses whose instability changed, sorted by name
+    pub score_changes: Vec<ScoreChange>,
+}
+
+impl AnalysisDiff {
+    /// Whether the analyses have the same packages, imports and scores
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Exports the diff as `text` or `json`
+    pub fn export(&self, format: &str) -> Result<String, AnalyzeError> {
+        match format {
+            "text" => Ok(self.format_text()),
+            "json" => Ok(serde_json::to_string_pretty(self)?),
+            _ => Err(AnalyzeError::UnsupportedFormat(format.to_string())),
+        }
+    }
+
+    /// One line per change, `+`/`-` for added and removed packages and
+    /// imports, `~` for score changes
+    fn format_text(&self) -> String {
+        if self.is_empty() {
+            return "No changes\n".to_string();
+        }
+
+        let mut output = String::new();
+        for package in &self.added_packages {
+            let _ = writeln!(output, "+ package {}", package);
+        }
+        for package in &self.removed_packages {
+            let _ = writeln!(output, "- package {}", package);
+        }
+        for edge in &self.added_edges {
+            let _ = writeln!(output, "+ import {} -> {}", edge.from, edge.to);
+        }
+        for edge in &self.removed_edges {
+            let _ = writeln!(output, "- import {} -> {}", edge.from, edge.to);
+        }
+        for change in &self.score_changes {
+            let _ = writeln!(
+                output,
+                "~ {}: {:.2} -> {:.2} ({:+.2})",
+                change.package,
+                change.before,
+                change.after,
+                change.after - change.before
+            );
+        }
+        output
+    }
+}
+
+impl DependencyAnalyzer {
+    /// Compares this analysis, the old one, with `new`.
+    ///
+    /// Packages are matched by name and only imports between analyzed
+    /// packages count as edges. Scores are computed like
+    /// `coupling_scores`, so neither analyzer needs
+    /// `calculate_coupling_scores` first.
+    pub fn diff(&self, new: &DependencyAnalyzer) -> AnalysisDiff {
+        let old_edges = self.import_edges();
+        let new_edges = new.import_edges();
+        let old_scores = self.coupling_scores();
+        let new_scores = new.coupling_scores();
+
+        let sorted = |names: Vec<&String>| {
+            let mut names: Vec<String> = names.into_iter().cloned().collect();
+            names.sort();
+            names
+        };
+        let added_packages = sorted(
+            new.packages
+                .keys()
+                .filter(|name| !self.packages.contains_key(*name))
+                .collect(),
+        );
+        let removed_packages = sorted(
+            self.packages
+                .keys()
+                .filter(|name| !new.packages.contains_key(*name))
+                .collect(),
+        );
+
+        let mut score_changes: Vec<ScoreChange> = old_scores
+            .iter()
+            .filter_map(|(package, &before)| {
+                let after = *new_scores.get(package)?;
+                ((after - before).abs() > f64::EPSILON).then(|| ScoreChange {
+                    package: package.clone(),
+                    before,
+                    after,
+                })
+            })
+            .collect();
+        score_changes.sort_by(|a, b| a.package.cmp(&b.package));
+
+        AnalysisDiff {
+            added_packages,
+            removed_packages,
+            added_edges: new_edges.difference(&old_edges).cloned().collect(),
+            removed_edges: old_edges.difference(&new_edges).cloned().collect(),
+            score_changes,
+        }
+    }
+
+    /// Returns every import between analyzed packages
+    fn import_edges(&self) -> BTreeSet<ImportEdge> {
+        self.packages
+            .keys()
+            .flat_map(|name| {
+                self.internal_imports(name)
+                    .into_iter()
+                    .map(move |import| ImportEdge {
+                        from: name.clone(),
+                        to: import.to_string(),
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_added_import_is_single_added_edge() {
+        let old = TestRepo::new()
+            .files(&[
+                ("app/app.go", "package app\nimport \"lib\""),
+                ("lib/lib.go", "package lib"),
+                ("util/util.go", "package util"),
+            ])
+            .analyze();
+        let new = TestRepo::new()
+            .files(&[
+                ("app/app.go", "package app\nimport \"lib\""),
+                ("lib/lib.go", "package lib\nimport \"util\""),
+                ("util/util.go", "package util"),
+            ])
+            .analyze();
+
+        let diff = old.diff(&new);
+        assert!(diff.added_packages.is_empty());
+        assert!(diff.removed_packages.is_empty());
+        assert!(diff.removed_edges.is_empty());
+        assert_eq!(
+            diff.added_edges,
+            vec![ImportEdge {
+                from: "lib".to_string(),
+                to: "util".to_string(),
+            }]
+        );
+        // lib now imports as much as it is imported
+        let lib = diff
+            .score_changes
+            .iter()
+            .find(|change| change.package == "lib")
+            .unwrap();
+        assert_eq!((lib.before, lib.after), (0.0, 0.5));
+
+        let text = diff.export("text").unwrap();
+        assert!(text.contains("+ import lib -> util\n"), "{}", text);
+        assert!(text.contains("~ lib: 0.00 -> 0.50 (+0.50)\n"), "{}", text);
+        let json: serde_json::Value = serde_json::from_str(&diff.export("json").unwrap()).unwrap();
+        assert_eq!(
+            json["added_edges"],
+            serde_json::json!([{ "from": "lib", "to": "util" }])
+        );
+
+        assert!(old.diff(&old).is_empty());
+        assert_eq!(old.diff(&old).export("text").unwrap(), "No changes\n");
+        assert!(diff.export("yaml").is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use super::{DependencyAnalyzer, ImportKind, Map, Set, escape_dot, normalize_import_path};
+
+/// Key to the DOT export, one example edge per style
+const DOT_LEGEND: &str = r#"  subgraph cluster_legend {
+    label="Legend";
+    node [shape=plaintext];
+    "legend_package" [label="package\ninstability, n/a if isolated"];
+    "legend_import" [label="imports"];
+    "legend_cycle" [label="imports within a cycle"];
+    "legend_test" [label="imported by tests only"];
+    "legend_blank" [label="blank import"];
+    "legend_weight" [label="more references"];
+    "legend_package" -> "legend_import";
+    "legend_package" -> "legend_cycle" [color=red];
+    "legend_package" -> "legend_test" [style=dashed];
+    "legend_package" -> "legend_blank" [color=gray];
+    "legend_package" -> "legend_weight" [penwidth=4.0];
+  }
+"#;
+
+/// How a package imports one of its dependencies, over all of its files
+#[derive(Debug, Default)]
+struct EdgeUsage {
+    /// Only `*_test.go` files import the dependency
+    test_only: bool,
+    /// Every import of the dependency is a blank import
+    blank: bool,
+    /// Qualified references to the dependency, see `weighted_efferent`
+    weight: usize,
+}
+
+impl DependencyAnalyzer {
+    /// Formats the import graph as a Graphviz digraph.
+    ///
+    /// Every package becomes a node labelled with its name and coupling
+    /// score, and every internal import an edge from importer to imported
+    /// package. Edges between packages of the same import cycle are drawn
+    /// red, edges only test files need dashed and blank imports gray, and
+    /// an edge's `penwidth` grows with the references to the imported
+    /// package, up to 4 for the most used import. Nodes and edges are
+    /// sorted by name. With `legend`, a `cluster_legend` subgraph
+    /// explaining the labels and edge styles follows the edges.
+    pub(super) fn format_dot_output(&self, legend: bool) -> String {
+        let mut names: Vec<&str> = self.packages.keys().map(String::as_str).collect();
+        names.sort();
+
+        let mut cycle_of: Map<String, usize> = Map::default();
+        for (index, cycle) in self.find_cycles().into_iter().enumerate() {
+            for name in cycle {
+                cycle_of.insert(name, index);
+            }
+        }
+
+        let mut output = String::from("digraph dependencies {\n");
+        for name in &names {
+            let package = &self.packages[*name];
+            let score = if package.isolated {
+                "n/a".to_string()
+            } else {
+                format!("{:.2}", package.coupling_score)
+            };
+            output.push_str(&format!(
+                "  \"{}\" [label=\"{}\\n{}\"];\n",
+                escape_dot(name),
+                escape_dot(name),
+                score
+            ));
+        }
+
+        let edges: Vec<(&str, &str, EdgeUsage)> = names
+            .iter()
+            .flat_map(|name| {
+                self.internal_imports(name)
+                    .into_iter()
+                    .map(|dependency| (*name, dependency, self.edge_usage(name, dependency)))
+            })
+            .collect();
+        let max_weight = edges.iter().map(|(_, _, usage)| usage.weight).max();
+
+        for (name, dependency, usage) in &edges {
+            let cyclic = cycle_of
+                .get(*name)
+                .is_some_and(|cycle| cycle_of.get(*dependency) == Some(cycle));
+            let mut attributes = Vec::new();
+            if cyclic {
+                attributes.push("color=red".to_string());
+            } else if usage.blank {
+                attributes.push("color=gray".to_string());
+            }
+            if usage.test_only {
+                attributes.push("style=dashed".to_string());
+            }
+            if let Some(max_weight) = max_weight.filter(|_| usage.weight > 0) {
+                let penwidth = 1.0 + 3.0 * usage.weight as f64 / max_weight as f64;
+                attributes.push(format!("penwidth={:.1}", penwidth));
+            }
+
+            output.push_str(&format!(
+                "  \"{}\" -> \"{}\"",
+                escape_dot(name),
+                escape_dot(dependency)
+            ));
+            if !attributes.is_empty() {
+                output.push_str(&format!(" [{}]", attributes.join(", ")));
+            }
+            output.push_str(";\n");
+        }
+
+        if legend {
+            output.push_str(DOT_LEGEND);
+        }
+        output.push_str("}\n");
+        output
+    }
+
+    /// Collects how `name` imports `dependency` from the import specs and
+    /// qualified references of each of its files
+    fn edge_usage(&self, name: &str, dependency: &str) -> EdgeUsage {
+        let package = &self.packages[name];
+        let mut usage = EdgeUsage {
+            test_only: true,
+            blank: true,
+            weight: 0,
+        };
+        let mut imported = false;
+        for file in &package.files {
+            let specs: Vec<_> = package
+                .file_import_specs
+                .get(file)
+                .into_iter()
+                .flatten()
+                .filter(|spec| {
+                    self.resolve_import(&normalize_import_path(&spec.path)) == Some(dependency)
+                })
+                .collect();
+            if specs.is_empty() {
+                continue;
+            }
+
+            imported = true;
+            let is_test = file
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .is_some_and(|file_name| file_name.ends_with("_test.go"));
+            usage.test_only &= is_test;
+            usage.blank &= specs.iter().all(|spec| spec.kind == ImportKind::Blank);
+            if let Some(qualifiers) = package.file_qualifiers.get(file) {
+                let bound: Set<&str> = specs.iter().filter_map(|spec| spec.bound_name()).collect();
+                usage.weight += bound
+                    .into_iter()
+                    .filter_map(|bound| qualifiers.get(bound))
+                    .sum::<usize>();
+            }
+        }
+
+        if imported {
+            usage
+        } else {
+            EdgeUsage::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyze::ExportOptions;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_dot_nodes_edges_and_cycles() {
+        let sources = [
+            ("app/app.go", "package app\nimport (\n\"lib\"\n\"fmt\"\n)"),
+            ("lib/lib.go", "package lib\nimport \"util\""),
+            ("util/util.go", "package util\nimport \"lib\""),
+            ("tools/tools.go", "package tools"),
+        ];
+        let mut analyzer = TestRepo::new().files(&sources).analyze();
+        analyzer.calculate_coupling_scores();
+
+        let output = analyzer
+            .export_analysis("dot", &Default::default())
+            .unwrap();
+        assert!(output.starts_with("digraph dependencies {\n"));
+        assert!(output.ends_with("}\n"));
+
+        let lines: Vec<&str> = output.lines().map(str::trim).collect();
+        let nodes = lines.iter().filter(|l| l.contains("[label=")).count();
+        let edges: Vec<&&str> = lines.iter().filter(|l| l.contains(" -> ")).collect();
+        assert_eq!(nodes, 4);
+        assert_eq!(edges.len(), 3);
+
+        assert!(lines.contains(&"\"app\" [label=\"app\\n1.00\"];"));
+        assert!(lines.contains(&"\"tools\" [label=\"tools\\nn/a\"];"));
+        assert!(lines.contains(&"\"app\" -> \"lib\";"));
+        assert!(lines.contains(&"\"lib\" -> \"util\" [color=red];"));
+        assert!(lines.contains(&"\"util\" -> \"lib\" [color=red];"));
+    }
+
+    #[test]
+    fn test_dot_legend_only_when_requested() {
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("app/app.go", "package app\nimport \"lib\""),
+                ("lib/lib.go", "package lib"),
+            ])
+            .analyze();
+        analyzer.calculate_coupling_scores();
+
+        let output = analyzer
+            .export_analysis("dot", &Default::default())
+            .unwrap();
+        assert!(!output.contains("cluster_legend"));
+
+        let options = ExportOptions {
+            legend: true,
+            ..Default::default()
+        };
+        let output = analyzer.export_analysis("dot", &options).unwrap();
+        assert!(output.contains("  subgraph cluster_legend {\n"));
+        assert!(output.contains("\"legend_package\" -> \"legend_test\" [style=dashed];"));
+        assert!(output.ends_with("  }\n}\n"));
+    }
+
+    #[test]
+    fn test_dot_edge_styles_and_weights() {
+        let sources = [
+            (
+                "app/app.go",
+                "package app\nimport (\n\"lib\"\n\"util\"\n_ \"driver\"\n)\nfunc A() { lib.A(); lib.B(); lib.C(); util.A() }",
+            ),
+            (
+                "app/app_test.go",
+                "package app\nimport \"testutil\"\nfunc T() { testutil.A() }",
+            ),
+            ("lib/lib.go", "package lib"),
+            ("util/util.go", "package util"),
+            ("driver/driver.go", "package driver"),
+            ("testutil/testutil.go", "package testutil"),
+        ];
+        let mut analyzer = TestRepo::new().files(&sources).analyze();
+        analyzer.calculate_coupling_scores();
+
+        let output = analyzer
+            .export_analysis("dot", &Default::default())
+            .unwrap();
+        let lines: Vec<&str> = output.lines().map(str::trim).collect();
+        assert!(lines.contains(&"\"app\" -> \"lib\" [penwidth=4.0];"));
+        assert!(lines.contains(&"\"app\" -> \"util\" [penwidth=2.0];"));
+        assert!(lines.contains(&"\"app\" -> \"driver\" [color=gray];"));
+        assert!(lines.contains(&"\"app\" -> \"testutil\" [style=dashed, penwidth=2.0];"));
+    }
+}
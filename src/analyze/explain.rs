@@ -0,0 +1,135 @@
+use std::fmt::Write;
+
+use super::DependencyAnalyzer;
+
+/// Instability at or above which a package is treated as unstable
+const UNSTABLE_THRESHOLD: f64 = 0.5;
+
+impl DependencyAnalyzer {
+    /// Explains in plain words why a package has its instability.
+    ///
+    /// Breaks I = Ce / (Ca + Ce) down into the imports and dependents behind
+    /// it and ends with a suggestion on how to move the score.
+    ///
+    /// # Returns
+    ///
+    /// * `None` if no package called `name` was analyzed
+    pub fn explain_instability(&self, name: &str) -> Option<String> {
+        let package = self.packages.get(name)?;
+        let counts = self
+            .coupling_counts()
+            .get(name)
+            .copied()
+            .unwrap_or_default();
+        let (afferent, efferent) = (counts.afferent, counts.efferent);
+        let instability = if afferent + efferent > 0 {
+            efferent as f64 / (afferent + efferent) as f64
+        } else {
+            0.0
+        };
+
+        let internal = self.internal_imports(name);
+        let mut dependents: Vec<&str> = self
+            .packages
+            .keys()
+            .map(String::as_str)
+            .filter(|other| *other != name && self.internal_imports(other).contains(&name))
+            .collect();
+        dependents.sort();
+
+        let mut output = String::new();
+        let _ = writeln!(
+            output,
+            "{} imports {} package(s) (Ce={}, {} internal) and is imported by {} (Ca={}), \
+             giving I = {} / ({} + {}) = {:.2}.",
+            name,
+            efferent,
+            efferent,
+            internal.len(),
+            afferent,
+            afferent,
+            efferent,
+            afferent,
+            efferent,
+            instability
+        );
+
+        let mut imports: Vec<&String> = package.imports.iter().collect();
+        imports.sort();
+        output.push_str("\nImports:\n");
+        if imports.is_empty() {
+            output.push_str("  (none)\n");
+        }
+        for import in imports {
+            let origin = match self.resolve_import(import) {
+                Some(_) => "internal",
+                None => "external",
+            };
+            let _ = writeln!(output, "  - {} ({})", import, origin);
+        }
+
+        output.push_str("\nImported by:\n");
+        if dependents.is_empty() {
+            output.push_str("  (none)\n");
+        }
+        for dependent in &dependents {
+            let _ = writeln!(output, "  - {}", dependent);
+        }
+
+        let suggestion = if afferent + efferent == 0 {
+            "It neither imports nor is imported by anything; check whether it is still needed."
+                .to_string()
+        } else if instability >= UNSTABLE_THRESHOLD {
+            format!(
+                "To make it more stable, reduce its efferent coupling by dropping or \
+                 inverting some of its {} import(s), or increase reuse so that more \
+                 packages depend on it.",
+                efferent
+            )
+        } else {
+            format!(
+                "It is relatively stable: {} package(s) depend on it, so keep its API \
+                 stable; changes here ripple out to its dependents.",
+                afferent
+            )
+        };
+        let _ = writeln!(output, "\nSuggestion: {}", suggestion);
+
+        Some(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_explanation_contains_coupling_counts() {
+        let analyzer = TestRepo::new()
+            .files(&[
+                (
+                    "app/app.go",
+                    "package app\nimport (\n\"lib\"\n\"util\"\n\"fmt\"\n)",
+                ),
+                ("lib/lib.go", "package lib\nimport \"util\""),
+                ("util/util.go", "package util"),
+            ])
+            .analyze();
+
+        let app = analyzer.explain_instability("app").unwrap();
+        assert!(app.contains("(Ce=3, 2 internal)"), "{}", app);
+        assert!(app.contains("(Ca=0)"), "{}", app);
+        assert!(app.contains("I = 3 / (0 + 3) = 1.00"), "{}", app);
+        assert!(app.contains("  - fmt (external)\n"));
+        assert!(app.contains("  - lib (internal)\n"));
+        assert!(app.contains("reduce its efferent coupling"));
+
+        let util = analyzer.explain_instability("util").unwrap();
+        assert!(util.contains("(Ce=0, 0 internal)"), "{}", util);
+        assert!(util.contains("(Ca=2)"), "{}", util);
+        assert!(util.contains("Imported by:\n  - app\n  - lib\n"));
+        assert!(util.contains("keep its API stable"));
+
+        assert!(analyzer.explain_instability("missing").is_none());
+    }
+}
@@ -0,0 +1,93 @@
+use std::fmt;
+
+use super::DependencyAnalyzer;
+
+/// Raw coupling count a `fan_report` ranks packages by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FanReport {
+    /// Afferent coupling (Ca): packages importing the package
+    FanIn,
+    /// Efferent coupling (Ce): imports of the package
+    FanOut,
+}
+
+impl std::str::FromStr for FanReport {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fanin" => Ok(FanReport::FanIn),
+            "fanout" => Ok(FanReport::FanOut),
+            _ => Err(format!("unknown report `{}` (expected fanin or fanout)", s)),
+        }
+    }
+}
+
+impl fmt::Display for FanReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FanReport::FanIn => write!(f, "fanin"),
+            FanReport::FanOut => write!(f, "fanout"),
+        }
+    }
+}
+
+impl DependencyAnalyzer {
+    /// Ranks packages by raw fan-in or fan-out, which points at "god
+    /// packages" the instability ratio hides: a package imported by fifty
+    /// others and one imported by two can share the same score.
+    ///
+    /// Counts are the same as `Ca`/`Ce` in detailed output, so options
+    /// such as `set_ignore_cycle_edges` apply.
+    ///
+    /// # Returns
+    ///
+    /// * `(package, count)` pairs, highest count first, then by name
+    pub fn fan_report(&self, report: FanReport) -> Vec<(String, usize)> {
+        let mut ranking: Vec<(String, usize)> = self
+            .coupling_counts()
+            .iter()
+            .map(|(name, counts)| {
+                let count = match report {
+                    FanReport::FanIn => counts.afferent,
+                    FanReport::FanOut => counts.efferent,
+                };
+                (name.clone(), count)
+            })
+            .collect();
+        ranking.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranking
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_hub_of_star_tops_fan_in() {
+        let mut analyzer = DependencyAnalyzer::new();
+        for spoke in ["a", "b", "c"] {
+            let source = format!("package {}\nimport (\n\"hub\"\n\"fmt\"\n)", spoke);
+            let path = format!("/p/{}/{}.go", spoke, spoke);
+            analyzer
+                .analyze_source(Path::new(&path), &source, 1)
+                .unwrap();
+        }
+        analyzer
+            .analyze_source(Path::new("/p/hub/hub.go"), "package hub", 1)
+            .unwrap();
+
+        let fan_in = analyzer.fan_report(FanReport::FanIn);
+        assert_eq!(fan_in[0], ("hub".to_string(), 3));
+        assert_eq!(fan_in[1], ("a".to_string(), 0));
+
+        let fan_out = analyzer.fan_report(FanReport::FanOut);
+        assert_eq!(fan_out[0], ("a".to_string(), 2));
+        assert_eq!(fan_out.last().unwrap(), &("hub".to_string(), 0));
+
+        assert_eq!("fanin".parse(), Ok(FanReport::FanIn));
+        assert!("fan-in".parse::<FanReport>().is_err());
+    }
+}
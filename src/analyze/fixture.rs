@@ -0,0 +1,183 @@
+//! Shared test fixtures and output snapshots for the export formats
+
+use std::path::Path;
+
+use super::DependencyAnalyzer;
+
+/// Source files of a small project, keyed by their path below the project
+/// root
+const FIXTURE_SOURCES: [(&str, &str); 5] = [
+    (
+        "app/app.go",
+        "package app\n\nimport (\n\t\"fmt\"\n\t\"lib\"\n\t\"util\"\n)\n\nfunc Render() {}\n",
+    ),
+    (
+        "lib/lib.go",
+        "package lib\n\nimport \"util\"\n\nfunc Parse() {}\n\nfunc helper() {}\n",
+    ),
+    (
+        "lib/store/store.go",
+        "package store\n\nimport (\n\t\"lib\"\n\t\"strings\"\n)\n\nfunc Get() {}\n\nfunc Put() {}\n",
+    ),
+    ("util/util.go", "package util\n\nfunc Clamp() {}\n"),
+    ("tools/tools.go", "package tools\n\nimport \"os\"\n"),
+];
+
+/// Builds an analyzer over a fixed five-package project with coupling
+/// scores already calculated:
+///
+/// ```text
+/// app -> lib, util, fmt
+/// lib -> util
+/// store -> lib, strings    (lives below lib/)
+/// util
+/// tools -> os              (leaf utility: imports only external packages)
+/// ```
+pub(crate) fn fixture_analyzer() -> DependencyAnalyzer {
+    let mut analyzer = TestRepo::new().files(&FIXTURE_SOURCES).analyze();
+    analyzer.calculate_coupling_scores();
+    analyzer
+}
+
+/// Builds a throwaway project on disk, one directory per package:
+///
+/// ```ignore
+/// let analyzer = TestRepo::new()
+///     .package("A", &["B"])
+///     .package("B", &[])
+///     .analyze();
+/// ```
+pub(crate) struct TestRepo {
+    root: tempfile::TempDir,
+}
+
+impl TestRepo {
+    pub(crate) fn new() -> Self {
+        Self {
+            root: tempfile::tempdir().expect("Failed to create temp dir"),
+        }
+    }
+
+    /// Root directory of the project
+    pub(crate) fn path(&self) -> &Path {
+        self.root.path()
+    }
+
+    /// Adds `NAME/NAME.go` declaring package `name` and importing `imports`
+    pub(crate) fn package(self, name: &str, imports: &[&str]) -> Self {
+        let mut source = format!("package {}\n", name);
+        if !imports.is_empty() {
+            source.push_str("\nimport (\n");
+            for import in imports {
+                source.push_str(&format!("\t\"{}\"\n", import));
+            }
+            source.push_str(")\n");
+        }
+        self.file(&format!("{0}/{0}.go", name), &source)
+    }
+
+    /// Adds a file with arbitrary contents at `relative` below the root
+    pub(crate) fn file(self, relative: &str, contents: &str) -> Self {
+        let path = self.root.path().join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+        self
+    }
+
+    /// Adds files with arbitrary contents, keyed by their path below the root
+    pub(crate) fn files(self, files: &[(&str, &str)]) -> Self {
+        files.iter().fold(self, |repo, (relative, contents)| {
+            repo.file(relative, contents)
+        })
+    }
+
+    /// Analyzes the project; coupling scores are left to the caller so
+    /// that settings can still be changed first.
+    ///
+    /// The files are removed once the `TestRepo` is dropped, so keep it
+    /// around when the test reads sources back.
+    pub(crate) fn analyze(&self) -> DependencyAnalyzer {
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer
+            .analyze_directory(self.root.path())
+            .expect("Failed to analyze test repo");
+        analyzer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::ExportOptions;
+
+    fn export(format: &str, detailed: bool) -> String {
+        let options = ExportOptions {
+            detailed,
+            ..Default::default()
+        };
+        fixture_analyzer()
+            .export_analysis(format, &options)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_text_snapshot() {
+        insta::assert_snapshot!(export("text", true));
+    }
+
+    #[test]
+    fn test_json_snapshot() {
+        insta::assert_snapshot!(export("json", true));
+    }
+
+    #[test]
+    fn test_opml_snapshot() {
+        insta::assert_snapshot!(export("opml", false));
+    }
+
+    #[test]
+    fn test_communities_snapshot() {
+        insta::assert_snapshot!(export("communities", false));
+    }
+
+    #[test]
+    fn test_hierarchy_snapshot() {
+        insta::assert_snapshot!(export("hierarchy", false));
+    }
+
+    #[test]
+    fn test_dgml_snapshot() {
+        insta::assert_snapshot!(export("dgml", false));
+    }
+
+    #[test]
+    fn test_dot_snapshot() {
+        insta::assert_snapshot!(export("dot", false));
+    }
+
+    #[test]
+    fn test_csv_snapshot() {
+        insta::assert_snapshot!(export("csv", true));
+    }
+
+    #[test]
+    fn test_mermaid_snapshot() {
+        insta::assert_snapshot!(export("mermaid", false));
+    }
+
+    #[test]
+    fn test_gha_matrix_snapshot() {
+        let plan = fixture_analyzer()
+            .export_deployment_plan("gha-matrix")
+            .unwrap();
+        insta::assert_snapshot!(plan);
+    }
+
+    #[test]
+    fn test_dot_deploy_snapshot() {
+        let plan = fixture_analyzer()
+            .export_deployment_plan("dot-deploy")
+            .unwrap();
+        insta::assert_snapshot!(plan);
+    }
+}
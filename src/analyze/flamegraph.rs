@@ -0,0 +1,87 @@
+use super::{DependencyAnalyzer, Set};
+
+impl DependencyAnalyzer {
+    /// Formats the dependency tree as folded stacks for flamegraph tools
+    /// such as `inferno-flamegraph` or `flamegraph.pl`.
+    ///
+    /// Every path from a package that nothing imports down through its
+    /// internal imports becomes one `a;b;c LOC` line, so stack depth is
+    /// dependency depth and the width of a frame is the lines of code of the
+    /// package plus everything below it. A package already on the current
+    /// path is not visited again, which keeps cycles finite; packages only
+    /// reachable through a cycle start stacks of their own.
+    pub(super) fn format_flamegraph_output(&self) -> String {
+        let mut names: Vec<&str> = self.packages.keys().map(String::as_str).collect();
+        names.sort();
+
+        let mut imported: Set<&str> = Set::default();
+        for name in &names {
+            imported.extend(
+                self.internal_imports(name)
+                    .into_iter()
+                    .filter(|dependency| dependency != name),
+            );
+        }
+
+        let mut output = String::new();
+        let mut reached: Set<&str> = Set::default();
+        let roots = names.iter().filter(|name| !imported.contains(*name));
+        for root in roots.chain(names.iter()) {
+            if !reached.contains(root) {
+                self.fold_stacks(&mut vec![root], &mut reached, &mut output);
+            }
+        }
+        output
+    }
+
+    /// Writes the folded stack of `path` and of every path extending it
+    fn fold_stacks<'a>(
+        &'a self,
+        path: &mut Vec<&'a str>,
+        reached: &mut Set<&'a str>,
+        output: &mut String,
+    ) {
+        let current = path[path.len() - 1];
+        reached.insert(current);
+        output.push_str(&format!(
+            "{} {}\n",
+            path.join(";"),
+            self.packages[current].lines_of_code
+        ));
+
+        for dependency in self.internal_imports(current) {
+            if !path.contains(&dependency) {
+                path.push(dependency);
+                self.fold_stacks(path, reached, output);
+                path.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_chain_becomes_nested_stacks() {
+        let mut analyzer = DependencyAnalyzer::new();
+        let sources = [
+            ("app", "package app\nimport \"lib\"", 30),
+            ("lib", "package lib\nimport \"util\"", 20),
+            ("util", "package util\nimport \"lib\"", 10),
+        ];
+        for (name, source, lines_of_code) in sources {
+            let path = format!("/p/{}/{}.go", name, name);
+            analyzer
+                .analyze_source(Path::new(&path), source, lines_of_code)
+                .unwrap();
+        }
+
+        let output = analyzer
+            .export_analysis("flamegraph", &Default::default())
+            .unwrap();
+        assert_eq!(output, "app 30\napp;lib 20\napp;lib;util 10\n");
+    }
+}
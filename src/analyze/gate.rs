@@ -0,0 +1,302 @@
+use std::fmt;
+use std::path::Path;
+
+use super::{AnalyzeError, DependencyAnalyzer};
+
+/// Number of indirect dependencies named by a `TransitiveViolation`
+const INDIRECT_SAMPLE: usize = 3;
+
+/// Package scores from a previously saved `--format json` report
+#[derive(Debug, Default)]
+pub struct Baseline {
+    packages: Vec<BaselinePackage>,
+}
+
+/// A saved JSON report: either a bare list of packages, or an object that
+/// also carries the report's `meta` information
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum SavedReport {
+    Packages(Vec<BaselinePackage>),
+    WithMeta { packages: Vec<BaselinePackage> },
+}
+
+/// The parts of a saved package analysis the gates compare against
+#[derive(Debug, serde::Deserialize)]
+struct BaselinePackage {
+    coupling_score: f64,
+}
+
+impl Baseline {
+    /// Reads a baseline from a JSON report file
+    pub fn load(path: &Path) -> Result<Self, AnalyzeError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses a baseline from the contents of a JSON report
+    pub fn from_json(json: &str) -> Result<Self, AnalyzeError> {
+        let packages = match serde_json::from_str(json)? {
+            SavedReport::Packages(packages) | SavedReport::WithMeta { packages } => packages,
+        };
+        Ok(Self { packages })
+    }
+
+    /// Mean instability over all baseline packages, 0.0 if there are none
+    pub fn average_instability(&self) -> f64 {
+        average(self.packages.iter().map(|p| p.coupling_score))
+    }
+}
+
+/// A package depending on more packages, directly or transitively, than
+/// the budget allows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransitiveViolation {
+    /// The offending package
+    pub package: String,
+    /// Number of analyzed packages it depends on, directly or transitively
+    pub count: usize,
+    /// Largest allowed number of transitive dependencies
+    pub max: usize,
+    /// Dependencies reached only through other packages, sorted by name
+    pub indirect: Vec<String>,
+}
+
+impl fmt::Display for TransitiveViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} depends on {} packages transitively (max {})",
+            self.package, self.count, self.max
+        )?;
+        if !self.indirect.is_empty() {
+            let sample: Vec<&str> = self
+                .indirect
+                .iter()
+                .take(INDIRECT_SAMPLE)
+                .map(String::as_str)
+                .collect();
+            let more = if self.indirect.len() > INDIRECT_SAMPLE {
+                ", ..."
+            } else {
+                ""
+            };
+            write!(f, ", indirectly {}{}", sample.join(", "), more)?;
+        }
+        Ok(())
+    }
+}
+
+impl DependencyAnalyzer {
+    /// Mean instability over all analyzed packages, 0.0 if there are none.
+    ///
+    /// Reflects the scores from the last `calculate_coupling_scores` call.
+    pub fn average_instability(&self) -> f64 {
+        average(self.packages.values().map(|p| p.coupling_score))
+    }
+
+    /// Returns how much the average instability grew compared to the
+    /// baseline; negative if the project became more stable
+    pub fn average_instability_regression(&self, baseline: &Baseline) -> f64 {
+        self.average_instability() - baseline.average_instability()
+    }
+
+    /// Finds packages whose instability from the last
+    /// `calculate_coupling_scores` call exceeds `max`.
+    ///
+    /// # Returns
+    ///
+    /// * `(package name, instability)` pairs, most unstable first
+    pub fn instability_violations(&self, max: f64) -> Vec<(String, f64)> {
+        let mut violations: Vec<(String, f64)> = self
+            .packages
+            .values()
+            .filter(|package| package.coupling_score > max)
+            .map(|package| (package.key.clone(), package.coupling_score))
+            .collect();
+
+        violations.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        violations
+    }
+
+    /// Finds packages depending on more than `max` analyzed packages,
+    /// directly or transitively. Import cycles are followed only once.
+    ///
+    /// # Returns
+    ///
+    /// * The violations, largest dependency set first
+    pub fn transitive_violations(&self, max: usize) -> Vec<TransitiveViolation> {
+        let mut violations: Vec<TransitiveViolation> = self
+            .packages
+            .keys()
+            .filter_map(|name| {
+                let dependencies = self.transitive_imports(name);
+                if dependencies.len() <= max {
+                    return None;
+                }
+                let direct = self.internal_imports(name);
+                Some(TransitiveViolation {
+                    package: name.clone(),
+                    count: dependencies.len(),
+                    max,
+                    indirect: dependencies
+                        .into_iter()
+                        .filter(|dependency| !direct.contains(dependency))
+                        .map(str::to_string)
+                        .collect(),
+                })
+            })
+            .collect();
+
+        violations.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.package.cmp(&b.package))
+        });
+        violations
+    }
+
+    /// Finds packages that directly import more than `max` packages.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - Largest allowed number of direct imports
+    /// * `internal_only` - Only count imports of analyzed packages, ignoring
+    ///   the standard library and external modules
+    ///
+    /// # Returns
+    ///
+    /// * `(package name, import count)` pairs, largest fan-out first
+    pub fn fan_out_violations(&self, max: usize, internal_only: bool) -> Vec<(String, usize)> {
+        let mut violations: Vec<(String, usize)> = self
+            .packages
+            .values()
+            .map(|package| {
+                let fan_out = if internal_only {
+                    self.internal_imports(&package.key).len()
+                } else {
+                    package.imports.len()
+                };
+                (package.key.clone(), fan_out)
+            })
+            .filter(|(_, fan_out)| *fan_out > max)
+            .collect();
+
+        violations.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        violations
+    }
+}
+
+/// Arithmetic mean, 0.0 for an empty sequence
+fn average(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = values.fold((0.0, 0usize), |(sum, count), v| (sum + v, count + 1));
+    if count == 0 { 0.0 } else { sum / count as f64 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_fan_out_violations() {
+        let analyzer = TestRepo::new()
+            .files(&[
+                (
+                    "app/app.go",
+                    "package app\nimport (\n\"fmt\"\n\"lib\"\n\"util\"\n)",
+                ),
+                ("lib/lib.go", "package lib\nimport \"fmt\""),
+                ("util/util.go", "package util"),
+            ])
+            .analyze();
+
+        assert_eq!(
+            analyzer.fan_out_violations(1, false),
+            vec![("app".to_string(), 3)]
+        );
+        assert_eq!(
+            analyzer.fan_out_violations(1, true),
+            vec![("app".to_string(), 2)]
+        );
+        assert!(analyzer.fan_out_violations(2, true).is_empty());
+    }
+
+    #[test]
+    fn test_instability_violations() {
+        // app: I = 1.0, lib: I = 1/2, util: I = 0.0
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("app/app.go", "package app\nimport \"lib\""),
+                ("lib/lib.go", "package lib\nimport \"util\""),
+                ("util/util.go", "package util"),
+            ])
+            .analyze();
+        analyzer.calculate_coupling_scores();
+
+        assert_eq!(
+            analyzer.instability_violations(0.8),
+            vec![("app".to_string(), 1.0)]
+        );
+        assert_eq!(analyzer.instability_violations(0.4).len(), 2);
+        assert!(analyzer.instability_violations(1.0).is_empty());
+    }
+
+    #[test]
+    fn test_transitive_violations_follow_cycles_once() {
+        // a -> b -> c -> d -> b
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("a/a.go", "package a\nimport \"b\""),
+                ("b/b.go", "package b\nimport \"c\""),
+                ("c/c.go", "package c\nimport \"d\""),
+                ("d/d.go", "package d\nimport \"b\""),
+            ])
+            .analyze();
+
+        let violations = analyzer.transitive_violations(2);
+        let counts: Vec<(&str, usize)> = violations
+            .iter()
+            .map(|v| (v.package.as_str(), v.count))
+            .collect();
+        assert_eq!(counts, vec![("a", 3)]);
+        assert_eq!(violations[0].indirect, vec!["c", "d"]);
+        assert_eq!(
+            violations[0].to_string(),
+            "a depends on 3 packages transitively (max 2), indirectly c, d"
+        );
+        assert_eq!(analyzer.transitive_violations(1).len(), 4);
+    }
+
+    #[test]
+    fn test_average_instability_regression() {
+        let baseline = Baseline::from_json(
+            r#"[
+                {"name": "a", "coupling_score": 1.0, "imports": ["lib"]},
+                {"name": "lib", "coupling_score": 0.0, "imports": []}
+            ]"#,
+        )
+        .unwrap();
+        assert!((baseline.average_instability() - 0.5).abs() < f64::EPSILON);
+
+        let with_meta = Baseline::from_json(
+            r#"{
+                "meta": {"version": "0.1.0", "paths": ["."], "options": {}},
+                "packages": [{"name": "a", "coupling_score": 1.0, "imports": []}]
+            }"#,
+        )
+        .unwrap();
+        assert!((with_meta.average_instability() - 1.0).abs() < f64::EPSILON);
+
+        // a: Ce=2, Ca=0 -> 1.0; lib: Ce=1, Ca=1 -> 0.5
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("a/a.go", "package a\nimport (\n\"fmt\"\n\"lib\"\n)"),
+                ("lib/lib.go", "package lib\nimport \"fmt\""),
+            ])
+            .analyze();
+        analyzer.calculate_coupling_scores();
+
+        assert!((analyzer.average_instability() - 0.75).abs() < f64::EPSILON);
+        assert!((analyzer.average_instability_regression(&baseline) - 0.25).abs() < f64::EPSILON);
+    }
+}
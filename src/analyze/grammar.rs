@@ -0,0 +1,89 @@
+use tree_sitter::{Language, Query};
+
+use super::SOURCE_QUERY;
+
+/// tree-sitter-go node types the built-in query matches, with what they
+/// hold
+const NODE_TYPES: [(&str, &str); 14] = [
+    (
+        "source_file",
+        "root of every file; parent of top-level declarations",
+    ),
+    ("package_clause", "`package name` at the top of a file"),
+    ("package_identifier", "the name in a package_clause"),
+    (
+        "import_declaration",
+        "an `import` keyword with one spec or a list",
+    ),
+    (
+        "import_spec_list",
+        "the parenthesized specs of a grouped import",
+    ),
+    (
+        "import_spec",
+        "one import; fields `name` (alias, `_` or `.`) and `path`",
+    ),
+    ("interpreted_string_literal", "a double-quoted import path"),
+    ("raw_string_literal", "a backquoted import path"),
+    ("function_declaration", "a top-level function; field `name`"),
+    (
+        "type_declaration",
+        "a `type` keyword with one spec or a list",
+    ),
+    ("type_spec", "one declared type; fields `name` and `type`"),
+    ("interface_type", "an `interface { ... }` type"),
+    (
+        "selector_expression",
+        "`x.Name`; field `operand` is the package for references to imports",
+    ),
+    ("qualified_type", "`pkg.Type`; field `package`"),
+];
+
+/// Describes the parts of the tree-sitter-go grammar the analyzer relies
+/// on, as a reference for writing queries against it.
+///
+/// Lists the relevant node types, the capture names of the built-in
+/// query, and the query itself.
+pub fn grammar_info() -> String {
+    let language: Language = tree_sitter_go::LANGUAGE.into();
+    let mut output = format!(
+        "Grammar: tree-sitter-go (ABI {})\n\n",
+        language.abi_version()
+    );
+
+    output.push_str("Node types:\n");
+    for (kind, description) in NODE_TYPES {
+        output.push_str(&format!("  {:<28}{}\n", kind, description));
+    }
+
+    output.push_str("\nCaptures:\n");
+    if let Ok(query) = Query::new(&language, SOURCE_QUERY) {
+        for name in query.capture_names() {
+            output.push_str(&format!("  @{}\n", name));
+        }
+    }
+
+    output.push_str("\nBuilt-in query:\n");
+    output.push_str(SOURCE_QUERY.trim_start_matches('\n'));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grammar_info_lists_nodes_and_captures() {
+        let info = grammar_info();
+        assert!(info.contains("\n  import_spec "));
+        assert!(info.contains("\n  @package\n"));
+        assert!(info.contains("\n  @import\n"));
+        assert!(info.contains("(import_spec_list"));
+
+        // Every listed node type must exist in the grammar
+        let language: Language = tree_sitter_go::LANGUAGE.into();
+        for (kind, _) in NODE_TYPES {
+            assert_ne!(language.id_for_node_kind(kind, true), 0, "{}", kind);
+        }
+    }
+}
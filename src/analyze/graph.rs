@@ -0,0 +1,624 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use super::{DependencyAnalyzer, Map, Set};
+
+/// Upper bound on local moving rounds; the deterministic update order
+/// normally converges in a handful of rounds.
+const MAX_MOVING_ROUNDS: usize = 100;
+
+impl DependencyAnalyzer {
+    /// Detects communities of closely related packages.
+    ///
+    /// Runs the local moving phase of the Louvain method over the undirected
+    /// projection of the import graph: every package starts in its own
+    /// community and repeatedly moves to the neighbouring community with the
+    /// largest modularity gain, until no move improves modularity. Packages
+    /// are visited in name order so the result is deterministic.
+    ///
+    /// # Returns
+    ///
+    /// * Communities as sorted lists of package names, largest community first
+    pub fn communities(&self) -> Vec<Vec<String>> {
+        let adjacency = self.undirected_adjacency();
+        let labels = assign_communities(&adjacency);
+
+        let mut groups: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for (name, label) in labels {
+            groups.entry(label).or_default().push(name.to_string());
+        }
+
+        let mut communities: Vec<Vec<String>> = groups.into_values().collect();
+        for community in &mut communities {
+            community.sort();
+        }
+        communities.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        communities
+    }
+
+    /// Computes the Newman modularity of the detected communities.
+    ///
+    /// Q = Σ_c [L_c/m - (d_c/2m)²], where m is the number of undirected
+    /// edges, L_c the number of edges inside community c and d_c the sum of
+    /// degrees of its members. Ranges from -0.5 to 1.0; higher values mean
+    /// the graph splits more cleanly into the detected communities.
+    pub fn modularity(&self) -> f64 {
+        let adjacency = self.undirected_adjacency();
+        let edge_count = adjacency.values().map(BTreeSet::len).sum::<usize>() as f64 / 2.0;
+        if edge_count == 0.0 {
+            return 0.0;
+        }
+
+        let labels = assign_communities(&adjacency);
+        let mut internal_edges: BTreeMap<&str, f64> = BTreeMap::new();
+        let mut degree_sums: BTreeMap<&str, f64> = BTreeMap::new();
+
+        for (name, neighbours) in &adjacency {
+            let label = labels[name];
+            *degree_sums.entry(label).or_default() += neighbours.len() as f64;
+            for neighbour in neighbours {
+                if labels[neighbour] == label {
+                    // Each internal edge is seen from both ends
+                    *internal_edges.entry(label).or_default() += 0.5;
+                }
+            }
+        }
+
+        degree_sums
+            .iter()
+            .map(|(label, degree_sum)| {
+                let internal = internal_edges.get(label).copied().unwrap_or(0.0);
+                internal / edge_count - (degree_sum / (2.0 * edge_count)).powi(2)
+            })
+            .sum()
+    }
+
+    /// Counts, for every package, how many packages depend on it directly
+    /// or transitively
+    pub(super) fn impact_counts(&self) -> BTreeMap<&str, usize> {
+        let mut dependents: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for name in self.packages.keys() {
+            for dependency in self.internal_imports(name) {
+                dependents.entry(dependency).or_default().insert(name);
+            }
+        }
+
+        self.packages
+            .keys()
+            .map(|name| {
+                let mut seen: BTreeSet<&str> = BTreeSet::new();
+                let mut stack = vec![name.as_str()];
+                while let Some(current) = stack.pop() {
+                    for &dependent in dependents.get(current).into_iter().flatten() {
+                        if dependent != name && seen.insert(dependent) {
+                            stack.push(dependent);
+                        }
+                    }
+                }
+                (name.as_str(), seen.len())
+            })
+            .collect()
+    }
+
+    /// Returns every analyzed package `name` depends on, directly or
+    /// transitively. Cycles are followed once, and `name` itself is left
+    /// out even when it sits on a cycle.
+    pub(super) fn transitive_imports(&self, name: &str) -> BTreeSet<&str> {
+        let mut seen: BTreeSet<&str> = BTreeSet::new();
+        let mut stack = vec![name];
+        while let Some(current) = stack.pop() {
+            for dependency in self.internal_imports(current) {
+                if dependency != name && seen.insert(dependency) {
+                    stack.push(dependency);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Returns every analyzed package `package` depends on, directly or
+    /// through other packages, i.e. everything a change to it could be
+    /// affected by. Terminates on cycles; `package` itself is never part
+    /// of the result.
+    pub fn transitive_dependencies(&self, package: &str) -> HashSet<String> {
+        self.transitive_imports(package)
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Finds the longest chain of imports, the critical path of a
+    /// deployment: every package on it has to be deployed after the one it
+    /// imports, so its length is the number of sequential deploy steps that
+    /// can't be avoided.
+    ///
+    /// Works on the condensation of the import graph, where the packages
+    /// of an import cycle form one step. Ties are broken by package name.
+    ///
+    /// # Returns
+    ///
+    /// * Package names from the importer at the top of the chain down to
+    ///   the package deployed first; the packages of a cycle on the chain
+    ///   follow each other in name order. Empty without packages.
+    pub fn longest_chain(&self) -> Vec<String> {
+        // Cycles first, then every other package on its own
+        let mut components = self.find_cycles();
+        let mut component_of: Map<String, usize> = components
+            .iter()
+            .enumerate()
+            .flat_map(|(index, cycle)| cycle.iter().map(move |name| (name.clone(), index)))
+            .collect();
+        let mut names: Vec<&String> = self.packages.keys().collect();
+        names.sort();
+        for name in names {
+            if !component_of.contains_key(name) {
+                component_of.insert(name.clone(), components.len());
+                components.push(vec![name.clone()]);
+            }
+        }
+
+        let mut successors: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); components.len()];
+        for (name, &component) in &component_of {
+            for import in self.internal_imports(name) {
+                let imported = component_of[import];
+                if imported != component {
+                    successors[component].insert(imported);
+                }
+            }
+        }
+
+        let mut chains = ChainState {
+            components: &components,
+            successors: &successors,
+            depth: vec![None; components.len()],
+            next: vec![None; components.len()],
+        };
+        let depths: Vec<(usize, usize)> = (0..components.len())
+            .map(|component| (chains.depth(component), component))
+            .collect();
+        let Some(mut current) = depths
+            .into_iter()
+            .reduce(|best, candidate| chains.prefer(best, candidate))
+            .map(|(_, component)| component)
+        else {
+            return Vec::new();
+        };
+
+        let mut chain = components[current].clone();
+        while let Some(next) = chains.next[current] {
+            chain.extend(components[next].iter().cloned());
+            current = next;
+        }
+        chain
+    }
+
+    /// Formats detected communities and the resulting modularity as text
+    pub(super) fn format_communities_output(&self) -> String {
+        let mut output = String::new();
+        for (index, community) in self.communities().iter().enumerate() {
+            output.push_str(&format!(
+                "Community {}: {}\n",
+                index + 1,
+                community.join(", ")
+            ));
+        }
+        output.push_str(&format!("\nModularity: {:.2}\n", self.modularity()));
+        output
+    }
+
+    /// Builds the undirected projection of the internal import graph,
+    /// ignoring self-imports
+    fn undirected_adjacency(&self) -> BTreeMap<&str, BTreeSet<&str>> {
+        let mut adjacency: BTreeMap<&str, BTreeSet<&str>> = self
+            .packages
+            .keys()
+            .map(|name| (name.as_str(), BTreeSet::new()))
+            .collect();
+
+        for name in self.packages.keys() {
+            for dependency in self.internal_imports(name) {
+                if dependency == name {
+                    continue;
+                }
+                adjacency.entry(name).or_default().insert(dependency);
+                adjacency.entry(dependency).or_default().insert(name);
+            }
+        }
+
+        adjacency
+    }
+
+    /// Finds the import cycles among analyzed packages with Tarjan's
+    /// strongly connected components algorithm.
+    ///
+    /// Each strongly connected component with more than one package, or a
+    /// package importing itself, is one cycle.
+    ///
+    /// # Returns
+    ///
+    /// * The package names of every cycle, sorted within each cycle and
+    ///   cycles sorted among themselves
+    pub fn find_cycles(&self) -> Vec<Vec<String>> {
+        self.cycles_without(None)
+    }
+
+    /// Counts the import cycles among analyzed packages, see `find_cycles`
+    pub fn cycle_count(&self) -> usize {
+        self.find_cycles().len()
+    }
+
+    /// Counts the imports left out of the coupling counts by
+    /// `set_ignore_cycle_edges`, 0 unless it is enabled.
+    ///
+    /// A package importing another package of its cycle through several
+    /// import paths counts once.
+    pub fn ignored_cycle_edges(&self) -> usize {
+        if self.ignore_cycle_edges {
+            self.cycle_edges().len()
+        } else {
+            0
+        }
+    }
+
+    /// Returns the imports between packages of the same cycle, including
+    /// self-imports, as `(importer, imported)` pairs
+    pub(super) fn cycle_edges(&self) -> Set<(&str, &str)> {
+        let cycle_of: Map<String, usize> = self
+            .find_cycles()
+            .into_iter()
+            .enumerate()
+            .flat_map(|(index, cycle)| cycle.into_iter().map(move |name| (name, index)))
+            .collect();
+
+        let mut edges = Set::default();
+        for name in self.packages.keys() {
+            let Some(cycle) = cycle_of.get(name) else {
+                continue;
+            };
+            for import in self.internal_imports(name) {
+                if cycle_of.get(import) == Some(cycle) {
+                    edges.insert((name.as_str(), import));
+                }
+            }
+        }
+        edges
+    }
+
+    /// Counts the import cycles left once the package `excluded` is removed
+    pub(super) fn count_cycles(&self, excluded: Option<&str>) -> usize {
+        self.cycles_without(excluded).len()
+    }
+
+    /// Finds the import cycles left once the package `excluded` is removed
+    fn cycles_without(&self, excluded: Option<&str>) -> Vec<Vec<String>> {
+        let graph: BTreeMap<&str, Vec<&str>> = self
+            .packages
+            .keys()
+            .map(String::as_str)
+            .filter(|name| Some(*name) != excluded)
+            .map(|name| {
+                let mut imports = self.internal_imports(name);
+                imports.retain(|import| Some(*import) != excluded);
+                (name, imports)
+            })
+            .collect();
+
+        let mut state = TarjanState::default();
+        for name in graph.keys() {
+            if !state.index.contains_key(name) {
+                state.visit(name, &graph);
+            }
+        }
+
+        let mut cycles: Vec<Vec<String>> = state
+            .cycles
+            .into_iter()
+            .map(|mut cycle| {
+                cycle.sort();
+                cycle.into_iter().map(str::to_string).collect()
+            })
+            .collect();
+        cycles.sort();
+        cycles
+    }
+}
+
+/// Assigns every node to a community, identified by the name of one of its
+/// members, by greedily maximizing modularity
+fn assign_communities<'a>(
+    adjacency: &BTreeMap<&'a str, BTreeSet<&'a str>>,
+) -> BTreeMap<&'a str, &'a str> {
+    let mut labels: BTreeMap<&str, &str> = adjacency.keys().map(|&name| (name, name)).collect();
+    let double_edges = adjacency.values().map(BTreeSet::len).sum::<usize>() as f64;
+    if double_edges == 0.0 {
+        return labels;
+    }
+
+    // Sum of member degrees per community
+    let mut totals: BTreeMap<&str, f64> = adjacency
+        .iter()
+        .map(|(&name, neighbours)| (name, neighbours.len() as f64))
+        .collect();
+
+    for _ in 0..MAX_MOVING_ROUNDS {
+        let mut changed = false;
+
+        for (&name, neighbours) in adjacency {
+            let degree = neighbours.len() as f64;
+            let current = labels[name];
+            *totals.entry(current).or_default() -= degree;
+
+            // Edges from this node into each neighbouring community
+            let mut links: BTreeMap<&str, f64> = BTreeMap::new();
+            links.insert(current, 0.0);
+            for neighbour in neighbours {
+                *links.entry(labels[neighbour]).or_default() += 1.0;
+            }
+
+            // Gain of joining a community, up to a constant factor. Staying
+            // wins ties, then the smallest label.
+            let gain = |label: &str, link: f64| link - totals[label] * degree / double_edges;
+            let mut best = current;
+            let mut best_gain = gain(current, links[current]);
+            for (&label, &link) in &links {
+                let candidate = gain(label, link);
+                if candidate > best_gain + f64::EPSILON {
+                    best = label;
+                    best_gain = candidate;
+                }
+            }
+
+            *totals.entry(best).or_default() += degree;
+            if best != current {
+                labels.insert(name, best);
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    labels
+}
+
+/// Memoized longest paths through the condensed import graph
+struct ChainState<'a> {
+    /// Sorted package names of each component
+    components: &'a [Vec<String>],
+    successors: &'a [BTreeSet<usize>],
+    /// Number of components on the longest chain starting at a component
+    depth: Vec<Option<usize>>,
+    /// Component following a component on its longest chain
+    next: Vec<Option<usize>>,
+}
+
+impl ChainState<'_> {
+    /// The condensation is acyclic, so the recursion ends
+    fn depth(&mut self, component: usize) -> usize {
+        if let Some(depth) = self.depth[component] {
+            return depth;
+        }
+
+        let successors = self.successors;
+        let mut best: Option<(usize, usize)> = None;
+        for &successor in &successors[component] {
+            let candidate = (self.depth(successor), successor);
+            best = Some(match best {
+                Some(best) => self.prefer(best, candidate),
+                None => candidate,
+            });
+        }
+
+        let depth = 1 + best.map_or(0, |(depth, _)| depth);
+        self.next[component] = best.map(|(_, successor)| successor);
+        self.depth[component] = Some(depth);
+        depth
+    }
+
+    /// Picks the deeper of two `(depth, component)` pairs, the one whose
+    /// first package name sorts first on a tie
+    fn prefer(&self, a: (usize, usize), b: (usize, usize)) -> (usize, usize) {
+        let name = |component: usize| &self.components[component][0];
+        if b.0 > a.0 || (b.0 == a.0 && name(b.1) < name(a.1)) {
+            b
+        } else {
+            a
+        }
+    }
+}
+
+/// Bookkeeping for Tarjan's strongly connected components algorithm
+#[derive(Default)]
+struct TarjanState<'a> {
+    next_index: usize,
+    index: Map<&'a str, usize>,
+    low_link: Map<&'a str, usize>,
+    stack: Vec<&'a str>,
+    on_stack: Map<&'a str, bool>,
+    cycles: Vec<Vec<&'a str>>,
+}
+
+impl<'a> TarjanState<'a> {
+    fn visit(&mut self, name: &'a str, graph: &BTreeMap<&'a str, Vec<&'a str>>) {
+        self.index.insert(name, self.next_index);
+        self.low_link.insert(name, self.next_index);
+        self.next_index += 1;
+        self.stack.push(name);
+        self.on_stack.insert(name, true);
+
+        for &import in &graph[name] {
+            if !self.index.contains_key(import) {
+                self.visit(import, graph);
+                let low = self.low_link[name].min(self.low_link[import]);
+                self.low_link.insert(name, low);
+            } else if self.on_stack.get(import).copied().unwrap_or(false) {
+                let low = self.low_link[name].min(self.index[import]);
+                self.low_link.insert(name, low);
+            }
+        }
+
+        if self.low_link[name] == self.index[name] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.insert(member, false);
+                component.push(member);
+                if member == name {
+                    break;
+                }
+            }
+            if component.len() > 1 || graph[name].contains(&name) {
+                self.cycles.push(component);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_two_separated_clusters() {
+        // Two triangles joined by a single bridge edge C -> X
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("A/A.go", "package A\nimport (\n\"B\"\n\"C\"\n)"),
+                ("B/B.go", "package B\nimport \"C\""),
+                ("C/C.go", "package C\nimport \"X\""),
+                ("X/X.go", "package X\nimport (\n\"Y\"\n\"Z\"\n)"),
+                ("Y/Y.go", "package Y\nimport \"Z\""),
+                ("Z/Z.go", "package Z"),
+            ])
+            .analyze();
+
+        let communities = analyzer.communities();
+        assert_eq!(
+            communities,
+            vec![
+                vec!["A".to_string(), "B".to_string(), "C".to_string()],
+                vec!["X".to_string(), "Y".to_string(), "Z".to_string()],
+            ]
+        );
+
+        // 7 edges: each triangle has 3 internal edges and degree sum 7
+        // Q = 2 * (3/7 - (7/14)^2) = 6/7 - 1/2
+        let expected = 6.0 / 7.0 - 0.5;
+        assert!((analyzer.modularity() - expected).abs() < 1e-9);
+
+        let output = analyzer
+            .export_analysis("communities", &Default::default())
+            .unwrap();
+        assert!(output.contains("Community 1: A, B, C\n"));
+        assert!(output.contains("Community 2: X, Y, Z\n"));
+    }
+
+    #[test]
+    fn test_find_cycles_returns_strongly_connected_components() {
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("X/X.go", "package X\nimport \"Y\""),
+                ("Y/Y.go", "package Y\nimport \"X\""),
+                ("Z/Z.go", "package Z\nimport \"X\""),
+            ])
+            .analyze();
+
+        assert_eq!(
+            analyzer.find_cycles(),
+            vec![vec!["X".to_string(), "Y".to_string()]]
+        );
+        assert_eq!(analyzer.cycle_count(), 1);
+    }
+
+    #[test]
+    fn test_ignore_cycle_edges_changes_instability() {
+        // X <-> Y, with Z -> X and Y -> W around the cycle
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("X/X.go", "package X\nimport \"Y\""),
+                ("Y/Y.go", "package Y\nimport (\n\"X\"\n\"W\"\n)"),
+                ("Z/Z.go", "package Z\nimport \"X\""),
+                ("W/W.go", "package W"),
+            ])
+            .analyze();
+        let score =
+            |analyzer: &DependencyAnalyzer, name: &str| analyzer.packages[name].coupling_score;
+
+        analyzer.calculate_coupling_scores();
+        assert!((score(&analyzer, "X") - 1.0 / 3.0).abs() < 1e-9);
+        assert!((score(&analyzer, "Y") - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(analyzer.ignored_cycle_edges(), 0);
+
+        analyzer.set_ignore_cycle_edges(true);
+        assert!(!analyzer.scores_up_to_date());
+        analyzer.calculate_coupling_scores();
+        assert_eq!(score(&analyzer, "X"), 0.0);
+        assert_eq!(score(&analyzer, "Y"), 1.0);
+        assert_eq!(score(&analyzer, "Z"), 1.0);
+        assert_eq!(analyzer.ignored_cycle_edges(), 2);
+    }
+
+    #[test]
+    fn test_isolated_packages_are_own_community() {
+        let analyzer = TestRepo::new()
+            .files(&[("A/A.go", "package A"), ("B/B.go", "package B")])
+            .analyze();
+
+        assert_eq!(
+            analyzer.communities(),
+            vec![vec!["A".to_string()], vec!["B".to_string()]]
+        );
+        assert_eq!(analyzer.modularity(), 0.0);
+    }
+
+    #[test]
+    fn test_transitive_dependencies() {
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("A/A.go", "package A\nimport \"B\""),
+                ("B/B.go", "package B\nimport \"C\""),
+                ("C/C.go", "package C\nimport \"A\""),
+                ("D/D.go", "package D\nimport \"C\""),
+            ])
+            .analyze();
+
+        let closure = |name| {
+            let mut packages: Vec<String> =
+                analyzer.transitive_dependencies(name).into_iter().collect();
+            packages.sort();
+            packages
+        };
+        // A -> B -> C -> A: the cycle ends the walk and A leaves itself out
+        assert_eq!(closure("A"), ["B", "C"]);
+        assert_eq!(closure("D"), ["A", "B", "C"]);
+        assert!(analyzer.transitive_dependencies("missing").is_empty());
+    }
+
+    #[test]
+    fn test_longest_chain_follows_deepest_imports() {
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("A/A.go", "package A\nimport (\n\"B\"\n\"D\"\n)"),
+                ("B/B.go", "package B\nimport \"C\""),
+                ("C/C.go", "package C\nimport \"D\""),
+                ("D/D.go", "package D"),
+            ])
+            .analyze();
+        assert_eq!(analyzer.longest_chain(), ["A", "B", "C", "D"]);
+
+        // The cycle B <-> C is one step but lists both packages
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("A/A.go", "package A\nimport \"B\""),
+                ("B/B.go", "package B\nimport \"C\""),
+                ("C/C.go", "package C\nimport (\n\"B\"\n\"D\"\n)"),
+                ("D/D.go", "package D"),
+                ("E/E.go", "package E\nimport \"D\""),
+            ])
+            .analyze();
+        assert_eq!(analyzer.longest_chain(), ["A", "B", "C", "D"]);
+
+        assert!(DependencyAnalyzer::new().longest_chain().is_empty());
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use super::{DependencyAnalyzer, Map};
+
+/// Coupling of all packages below one directory, treated as a single unit
+#[derive(Debug, Default, PartialEq)]
+struct DirectoryCoupling {
+    /// Packages whose files live directly in this directory
+    packages: Vec<String>,
+    /// Packages outside the subtree that import a package inside it
+    afferent: BTreeSet<String>,
+    /// Imports from packages inside the subtree that point outside it
+    efferent: BTreeSet<String>,
+}
+
+/// How a package's imports relate to its place in the directory tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, schemars::JsonSchema)]
+pub(super) struct EdgeDirections {
+    /// Imports of packages in a subdirectory
+    pub(super) downward: usize,
+    /// Imports of packages in an unrelated subtree
+    pub(super) sideways: usize,
+    /// Imports of packages in an ancestor directory
+    pub(super) upward: usize,
+    /// Share of classified imports that point downward, 0.0 if there are none
+    pub(super) downward_ratio: f64,
+}
+
+impl DependencyAnalyzer {
+    /// Classifies every internal import by the relative position of the
+    /// importing and imported package directories.
+    ///
+    /// Imports between packages sharing a directory, or of packages whose
+    /// directory is unknown, aren't classified.
+    pub(super) fn edge_directions(&self) -> Map<&str, EdgeDirections> {
+        let directories = self.package_directories();
+
+        self.packages
+            .keys()
+            .map(|name| {
+                let mut directions = EdgeDirections::default();
+                let Some(dir) = directories.get(name.as_str()) else {
+                    return (name.as_str(), directions);
+                };
+
+                for dependency in self.internal_imports(name) {
+                    match directories.get(dependency) {
+                        Some(target) if target == dir => {}
+                        Some(target) if target.starts_with(dir) => directions.downward += 1,
+                        Some(target) if dir.starts_with(target) => directions.upward += 1,
+                        Some(_) => directions.sideways += 1,
+                        None => {}
+                    }
+                }
+
+                let classified = directions.downward + directions.sideways + directions.upward;
+                if classified > 0 {
+                    directions.downward_ratio = directions.downward as f64 / classified as f64;
+                }
+                (name.as_str(), directions)
+            })
+            .collect()
+    }
+
+    /// Aggregates coupling for every directory of the project tree.
+    ///
+    /// A package's edges bubble up to all of its ancestor directories; an edge
+    /// only counts for a directory if it crosses that directory's boundary.
+    /// Directories are keyed by their path relative to the deepest directory
+    /// containing all packages, so the root is the empty path.
+    fn directory_coupling(&self) -> BTreeMap<PathBuf, DirectoryCoupling> {
+        let directories = self.package_directories();
+        let Some(root) = common_ancestor(directories.values().copied()) else {
+            return BTreeMap::new();
+        };
+
+        // Every directory from the root down to each package's directory
+        let mut members: BTreeMap<PathBuf, BTreeSet<&str>> = BTreeMap::new();
+        let mut nodes: BTreeMap<PathBuf, DirectoryCoupling> = BTreeMap::new();
+        for (&name, dir) in &directories {
+            let relative = dir.strip_prefix(&root).unwrap_or(dir).to_path_buf();
+            nodes
+                .entry(relative.clone())
+                .or_default()
+                .packages
+                .push(name.to_string());
+
+            let mut current = Some(relative.as_path());
+            while let Some(path) = current {
+                members.entry(path.to_path_buf()).or_default().insert(name);
+                nodes.entry(path.to_path_buf()).or_default();
+                current = path.parent();
+            }
+        }
+
+        for (path, node) in nodes.iter_mut() {
+            node.packages.sort();
+            let inside = &members[path];
+
+            for package in self.packages.values() {
+                let is_member = inside.contains(package.key.as_str());
+                for import in &package.imports {
+                    match self.resolve_import(import) {
+                        Some(target) if is_member && !inside.contains(target) => {
+                            node.efferent.insert(target.to_string());
+                        }
+                        Some(target) if !is_member && inside.contains(target) => {
+                            node.afferent.insert(package.key.clone());
+                        }
+                        None if is_member => {
+                            node.efferent.insert(import.clone());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        nodes
+    }
+
+    /// Formats aggregated directory coupling as an indented tree
+    pub(super) fn format_hierarchy_output(&self) -> String {
+        let mut output = String::new();
+
+        for (path, node) in self.directory_coupling() {
+            let depth = path.components().count();
+            let label = match path.file_name() {
+                Some(name) => format!("{}/", name.to_string_lossy()),
+                None => "./".to_string(),
+            };
+            let afferent = node.afferent.len();
+            let efferent = node.efferent.len();
+            let instability = if afferent + efferent > 0 {
+                efferent as f64 / (afferent + efferent) as f64
+            } else {
+                0.0
+            };
+
+            output.push_str(&format!(
+                "{}{} (Ca: {}, Ce: {}, I: {:.2})",
+                "  ".repeat(depth),
+                label,
+                afferent,
+                efferent,
+                instability
+            ));
+            if !node.packages.is_empty() {
+                output.push_str(&format!(" [{}]", node.packages.join(", ")));
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+/// Returns the deepest directory containing every given path
+fn common_ancestor<'a>(mut paths: impl Iterator<Item = &'a Path>) -> Option<PathBuf> {
+    let mut ancestor = paths.next()?.to_path_buf();
+    for path in paths {
+        while !path.starts_with(&ancestor) {
+            if !ancestor.pop() {
+                break;
+            }
+        }
+    }
+    Some(ancestor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::ExportOptions;
+
+    fn write_package(root: &Path, dir: &str, source: &str) -> PathBuf {
+        let dir = root.join(dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("pkg.go");
+        std::fs::write(&file, source).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_parent_aggregates_external_edges() {
+        let root = tempfile::tempdir().expect("Failed to create temp dir");
+        let files = [
+            write_package(
+                root.path(),
+                "svc/a",
+                "package a\nimport (\n\"fmt\"\n\"lib\"\n)",
+            ),
+            write_package(
+                root.path(),
+                "svc/b",
+                "package b\nimport (\n\"a\"\n\"lib\"\n)",
+            ),
+            write_package(root.path(), "lib", "package lib"),
+        ];
+
+        let mut analyzer = DependencyAnalyzer::new();
+        for file in &files {
+            analyzer.analyze_file(file).unwrap();
+        }
+
+        let nodes = analyzer.directory_coupling();
+        let svc = &nodes[Path::new("svc")];
+        let a = &nodes[Path::new("svc/a")];
+        let b = &nodes[Path::new("svc/b")];
+
+        // svc's efferent set is the union of its children's external edges,
+        // minus the edge b -> a that stays inside svc
+        let members: BTreeSet<String> = ["a".to_string(), "b".to_string()].into();
+        let union: BTreeSet<String> = a
+            .efferent
+            .union(&b.efferent)
+            .filter(|target| !members.contains(*target))
+            .cloned()
+            .collect();
+        assert_eq!(svc.efferent, union);
+        assert_eq!(svc.efferent.len(), 2);
+        assert_eq!(a.afferent, BTreeSet::from(["b".to_string()]));
+        assert!(svc.afferent.is_empty());
+
+        let lib = &nodes[Path::new("lib")];
+        assert_eq!(lib.afferent.len(), 2);
+
+        let output = analyzer
+            .export_analysis("hierarchy", &Default::default())
+            .unwrap();
+        assert!(output.starts_with("./ (Ca: 0, Ce: 1, I: 1.00)\n"));
+        assert!(output.contains("  svc/ (Ca: 0, Ce: 2, I: 1.00)\n"));
+        assert!(output.contains("    a/ (Ca: 1, Ce: 2, I: 0.67) [a]\n"));
+    }
+
+    #[test]
+    fn test_edge_directions() {
+        let root = tempfile::tempdir().expect("Failed to create temp dir");
+        let files = [
+            write_package(
+                root.path(),
+                "app",
+                "package app\nimport (\n\"store\"\n\"handlers\"\n\"fmt\"\n)",
+            ),
+            write_package(
+                root.path(),
+                "app/handlers",
+                "package handlers\nimport \"app\"",
+            ),
+            write_package(root.path(), "store", "package store"),
+        ];
+
+        let mut analyzer = DependencyAnalyzer::new();
+        for file in &files {
+            analyzer.analyze_file(file).unwrap();
+        }
+
+        let directions = analyzer.edge_directions();
+        let app = directions["app"];
+        assert_eq!((app.downward, app.sideways, app.upward), (1, 1, 0));
+        assert!((app.downward_ratio - 0.5).abs() < f64::EPSILON);
+
+        let handlers = directions["handlers"];
+        assert_eq!(
+            (handlers.downward, handlers.sideways, handlers.upward),
+            (0, 0, 1)
+        );
+        assert_eq!(directions["store"], EdgeDirections::default());
+
+        let options = ExportOptions {
+            detailed: true,
+            ..Default::default()
+        };
+        let output = analyzer.export_analysis("text", &options).unwrap();
+        assert!(
+            output.contains("Edge Directions: 1 downward, 1 sideways, 0 upward (50% downward)\n")
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use super::{DependencyAnalyzer, PackageAnalysis, escape_xml};
+
+/// Column headings of the package table, in order
+const COLUMNS: [&str; 7] = [
+    "Package",
+    "Instability",
+    "Ca",
+    "Ce",
+    "Abstractness",
+    "Distance",
+    "Imports",
+];
+
+/// Sorts the package table by the clicked column, toggling the direction
+/// on repeated clicks; numeric columns compare as numbers
+const SORT_SCRIPT: &str = r#"<script>
+document.querySelectorAll("th").forEach((th, column) => {
+  th.addEventListener("click", () => {
+    const body = th.closest("table").tBodies[0];
+    const ascending = th.dataset.order !== "asc";
+    th.dataset.order = ascending ? "asc" : "desc";
+    const key = (row) => {
+      const text = row.cells[column].textContent;
+      const number = parseFloat(text);
+      return isNaN(number) ? text : number;
+    };
+    [...body.rows]
+      .sort((a, b) => (key(a) > key(b) ? 1 : key(a) < key(b) ? -1 : 0) * (ascending ? 1 : -1))
+      .forEach((row) => body.appendChild(row));
+  });
+});
+</script>
+"#;
+
+/// Renders the `<pre class="mermaid">` graph; the only part of the page
+/// loaded from outside
+const MERMAID_SCRIPT: &str = r#"<script type="module">
+import mermaid from "https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs";
+mermaid.initialize({ startOnLoad: true });
+</script>
+"#;
+
+impl DependencyAnalyzer {
+    /// Formats the reported packages as a standalone HTML page, for sharing
+    /// an analysis with people who don't run the tool.
+    ///
+    /// The page holds a table of the reported packages, sortable by
+    /// clicking a column heading, and the import graph in Mermaid syntax,
+    /// rendered by the Mermaid library when the page is opened online.
+    /// Package names are escaped everywhere.
+    pub(super) fn format_html_output(&self, results: &[PackageAnalysis]) -> String {
+        let mut output = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+        output.push_str("<meta charset=\"utf-8\">\n");
+        output.push_str("<title>Dependency analysis</title>\n");
+        output.push_str(
+            "<style>\n\
+             body { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; }\n\
+             th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: right; }\n\
+             th { cursor: pointer; background: #f0f0f0; }\n\
+             td:first-child { text-align: left; }\n\
+             </style>\n",
+        );
+        output.push_str("</head>\n<body>\n<h1>Dependency analysis</h1>\n");
+
+        output.push_str("<table>\n<thead>\n<tr>");
+        for column in COLUMNS {
+            output.push_str(&format!("<th>{}</th>", column));
+        }
+        output.push_str("</tr>\n</thead>\n<tbody>\n");
+        for result in results {
+            let metrics = &result.metrics;
+            output.push_str(&format!(
+                "<tr><td>{}</td><td>{:.2}</td><td>{}</td><td>{}</td><td>{:.2}</td><td>{:.2}</td><td>{}</td></tr>\n",
+                escape_xml(&result.name),
+                metrics.instability,
+                metrics.afferent_coupling,
+                metrics.efferent_coupling,
+                metrics.abstractness,
+                metrics.distance,
+                result.imports.len()
+            ));
+        }
+        output.push_str("</tbody>\n</table>\n");
+
+        output.push_str("<h2>Import graph</h2>\n<pre class=\"mermaid\">\n");
+        output.push_str(&escape_xml(&self.format_mermaid_output(false)));
+        output.push_str("</pre>\n");
+
+        output.push_str(SORT_SCRIPT);
+        output.push_str(MERMAID_SCRIPT);
+        output.push_str("</body>\n</html>\n");
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_html_table_has_row_per_package() {
+        let sources = [
+            ("app/app.go", "package app\nimport \"lib\""),
+            ("lib/lib.go", "package lib"),
+            ("tools/tools.go", "package tools"),
+        ];
+        let mut analyzer = TestRepo::new().files(&sources).analyze();
+        analyzer.calculate_coupling_scores();
+
+        let output = analyzer
+            .export_analysis("html", &Default::default())
+            .unwrap();
+        assert!(output.starts_with("<!DOCTYPE html>"));
+        assert!(output.contains("<table>"));
+
+        let rows: Vec<&str> = output
+            .lines()
+            .filter(|line| line.starts_with("<tr><td>"))
+            .collect();
+        assert_eq!(rows.len(), 3);
+        assert!(
+            rows.iter()
+                .any(|row| row.starts_with("<tr><td>app</td><td>1.00</td>"))
+        );
+        // The Mermaid graph is escaped like package names
+        assert!(output.contains("app[&quot;app (1.00)&quot;]"));
+        assert!(output.contains("app --&gt; lib"));
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use super::DependencyAnalyzer;
+
+/// Kinds of architectural smells reported by `DependencyAnalyzer::lint`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintKind {
+    /// A package imports a package located in one of its ancestor directories
+    AncestorImport,
+    /// An import path is bound to different aliases in different files
+    InconsistentAlias,
+    /// A file imports the same path more than once
+    DuplicateImport,
+}
+
+/// A single lint finding
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct LintWarning {
+    /// Package the finding is reported for
+    pub package: String,
+    /// Kind of the finding
+    pub kind: LintKind,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl std::fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.package, self.message)
+    }
+}
+
+/// Selects which lint checks are run
+#[derive(Debug, Clone)]
+pub struct LintOptions {
+    /// Flag packages importing a package from an ancestor directory
+    pub ancestor_imports: bool,
+    /// Flag import paths aliased differently across files
+    pub alias_consistency: bool,
+    /// Flag files importing the same path more than once
+    pub duplicate_imports: bool,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            ancestor_imports: true,
+            alias_consistency: true,
+            duplicate_imports: false,
+        }
+    }
+}
+
+impl DependencyAnalyzer {
+    /// Runs the enabled lint checks over the analyzed packages.
+    ///
+    /// # Returns
+    ///
+    /// * Findings sorted by package name
+    pub fn lint(&self, options: &LintOptions) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+
+        if options.ancestor_imports {
+            warnings.extend(self.ancestor_imports());
+        }
+        if options.alias_consistency {
+            warnings.extend(self.inconsistent_aliases());
+        }
+        if options.duplicate_imports {
+            warnings.extend(self.duplicate_imports());
+        }
+
+        warnings.sort_by(|a, b| {
+            a.package
+                .cmp(&b.package)
+                .then_with(|| a.message.cmp(&b.message))
+        });
+        warnings
+    }
+
+    /// Finds imports of a package whose directory is an ancestor of the
+    /// importing package's directory, e.g. `a/b/c` importing `a/b`
+    fn ancestor_imports(&self) -> Vec<LintWarning> {
+        let directories = self.package_directories();
+        let mut warnings = Vec::new();
+
+        for name in self.packages.keys() {
+            let Some(dir) = directories.get(name.as_str()) else {
+                continue;
+            };
+
+            for dependency in self.internal_imports(name) {
+                let Some(dependency_dir) = directories.get(dependency) else {
+                    continue;
+                };
+
+                if dir != dependency_dir && dir.starts_with(dependency_dir) {
+                    warnings.push(LintWarning {
+                        package: name.clone(),
+                        kind: LintKind::AncestorImport,
+                        message: format!("imports its ancestor package {}", dependency),
+                    });
+                }
+            }
+        }
+
+        warnings
+    }
+
+    /// Finds import paths bound to more than one alias across all files.
+    ///
+    /// Only explicit aliases are compared; `_` and `.` imports change what
+    /// the import does rather than its name and are left out. Findings are
+    /// reported for the imported path and list every alias with the files
+    /// using it.
+    fn inconsistent_aliases(&self) -> Vec<LintWarning> {
+        let mut aliases: BTreeMap<&str, BTreeMap<&str, BTreeSet<&Path>>> = BTreeMap::new();
+        for package in self.packages.values() {
+            for (file, specs) in &package.file_import_specs {
+                for spec in specs {
+                    let Some(alias) = spec.alias.as_deref() else {
+                        continue;
+                    };
+                    if alias != "_" && alias != "." {
+                        aliases
+                            .entry(&spec.path)
+                            .or_default()
+                            .entry(alias)
+                            .or_default()
+                            .insert(file);
+                    }
+                }
+            }
+        }
+
+        aliases
+            .into_iter()
+            .filter(|(_, by_alias)| by_alias.len() > 1)
+            .map(|(path, by_alias)| {
+                let uses: Vec<String> = by_alias
+                    .iter()
+                    .map(|(alias, files)| {
+                        let files: Vec<String> = files
+                            .iter()
+                            .map(|file| file.display().to_string())
+                            .collect();
+                        format!("{} ({})", alias, files.join(", "))
+                    })
+                    .collect();
+                LintWarning {
+                    package: path.to_string(),
+                    kind: LintKind::InconsistentAlias,
+                    message: format!("aliased inconsistently as {}", uses.join(", ")),
+                }
+            })
+            .collect()
+    }
+
+    /// Finds files importing the same path more than once, which the
+    /// deduplicated `imports` of a package would otherwise hide. Aliases
+    /// don't matter, so `f "fmt"` next to `"fmt"` is a duplicate too.
+    fn duplicate_imports(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        for package in self.packages.values() {
+            let mut files: Vec<&PathBuf> = package.file_import_specs.keys().collect();
+            files.sort();
+
+            for file in files {
+                let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+                for spec in &package.file_import_specs[file] {
+                    *counts.entry(&spec.path).or_default() += 1;
+                }
+                for (path, count) in counts.into_iter().filter(|(_, count)| *count > 1) {
+                    warnings.push(LintWarning {
+                        package: package.key.clone(),
+                        kind: LintKind::DuplicateImport,
+                        message: format!("{} imports {} {} times", file.display(), path, count),
+                    });
+                }
+            }
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_ancestor_import_is_flagged() {
+        let root = tempfile::tempdir().expect("Failed to create temp dir");
+        let b_dir = root.path().join("a").join("b");
+        let c_dir = b_dir.join("c");
+        let d_dir = b_dir.join("d");
+        std::fs::create_dir_all(&c_dir).unwrap();
+        std::fs::create_dir_all(&d_dir).unwrap();
+
+        // a/b/c imports its ancestor a/b; a/b/d imports its sibling a/b/c
+        std::fs::write(b_dir.join("b.go"), "package b").unwrap();
+        std::fs::write(c_dir.join("c.go"), "package c\nimport \"b\"").unwrap();
+        std::fs::write(d_dir.join("d.go"), "package d\nimport \"c\"").unwrap();
+
+        let mut analyzer = DependencyAnalyzer::new();
+        analyzer.analyze_file(&b_dir.join("b.go")).unwrap();
+        analyzer.analyze_file(&c_dir.join("c.go")).unwrap();
+        analyzer.analyze_file(&d_dir.join("d.go")).unwrap();
+
+        let warnings = analyzer.lint(&LintOptions::default());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].package, "c");
+        assert_eq!(warnings[0].kind, LintKind::AncestorImport);
+
+        let options = LintOptions {
+            ancestor_imports: false,
+            ..Default::default()
+        };
+        assert!(analyzer.lint(&options).is_empty());
+    }
+
+    #[test]
+    fn test_inconsistent_alias_is_flagged() {
+        let sources = [
+            ("a/a.go", "package a\nimport j \"encoding/json\""),
+            (
+                "b/b.go",
+                "package b\nimport (\njson \"encoding/json\"\n_ \"embed\"\n)",
+            ),
+            (
+                "b/c.go",
+                "package b\nimport (\n\"encoding/json\"\n. \"embed\"\n)",
+            ),
+        ];
+        let repo = TestRepo::new().files(&sources);
+        let analyzer = repo.analyze();
+
+        let warnings = analyzer.lint(&LintOptions::default());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, LintKind::InconsistentAlias);
+        assert_eq!(
+            warnings[0].to_string(),
+            format!(
+                "encoding/json: aliased inconsistently as j ({}), json ({})",
+                repo.path().join("a/a.go").display(),
+                repo.path().join("b/b.go").display()
+            )
+        );
+
+        let options = LintOptions {
+            alias_consistency: false,
+            ..Default::default()
+        };
+        assert!(analyzer.lint(&options).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_import_is_flagged() {
+        let sources = [
+            (
+                "a/a.go",
+                "package a\nimport \"fmt\"\nimport (\n\"fmt\"\n\"strings\"\n)",
+            ),
+            // The same import in two files of a package is fine
+            ("a/b.go", "package a\nimport \"strings\""),
+        ];
+        let repo = TestRepo::new().files(&sources);
+        let analyzer = repo.analyze();
+        assert_eq!(analyzer.packages["a"].imports.len(), 2);
+
+        assert!(analyzer.lint(&LintOptions::default()).is_empty());
+
+        let options = LintOptions {
+            duplicate_imports: true,
+            ..Default::default()
+        };
+        let warnings = analyzer.lint(&options);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, LintKind::DuplicateImport);
+        assert_eq!(
+            warnings[0].to_string(),
+            format!(
+                "a: {} imports fmt 2 times",
+                repo.path().join("a/a.go").display()
+            )
+        );
+    }
+}
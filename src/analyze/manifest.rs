@@ -0,0 +1,86 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{DependencyAnalyzer, PackageAnalysis};
+
+/// Group of imports whose path doesn't start with a domain, such as `fmt`
+const STANDARD_LIBRARY: &str = "std";
+
+impl DependencyAnalyzer {
+    /// Formats the external imports of every reported package as JSON.
+    ///
+    /// Maps each package to its imports of packages outside the project,
+    /// grouped by module prefix (see `module_prefix`), for feeding into
+    /// vulnerability scanners. Packages, groups and imports are sorted.
+    pub(super) fn format_deps_manifest(
+        &self,
+        results: &[PackageAnalysis],
+    ) -> Result<String, serde_json::Error> {
+        let manifest: BTreeMap<&str, BTreeMap<&str, BTreeSet<&str>>> = results
+            .iter()
+            .map(|result| {
+                let mut groups: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+                for import in &self.packages[&result.key].imports {
+                    if self.resolve_import(import).is_none() {
+                        groups
+                            .entry(module_prefix(import))
+                            .or_default()
+                            .insert(import);
+                    }
+                }
+                (result.key.as_str(), groups)
+            })
+            .collect();
+        serde_json::to_string_pretty(&manifest)
+    }
+}
+
+/// Module prefix an import is grouped under: its first two path segments
+/// when it starts with a domain (`github.com/org`, `gno.land/p`), or
+/// `std` for standard library imports
+fn module_prefix(import: &str) -> &str {
+    let mut segments = import.splitn(3, '/');
+    let host = segments.next().unwrap_or_default();
+    if !host.contains('.') {
+        return STANDARD_LIBRARY;
+    }
+    match segments.next() {
+        Some(owner) => &import[..host.len() + 1 + owner.len()],
+        None => host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_external_imports_grouped_by_module_prefix() {
+        let app = "package app\nimport (\n\"fmt\"\n\"lib\"\n\
+                   \"github.com/org/repo\"\n\"github.com/org/other/sub\"\n\"github.com/else/x\"\n)";
+        let analyzer = TestRepo::new()
+            .files(&[("app/app.go", app), ("lib/lib.go", "package lib")])
+            .analyze();
+
+        let output = analyzer
+            .export_analysis("deps-manifest", &Default::default())
+            .unwrap();
+        let manifest: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            manifest["app"],
+            serde_json::json!({
+                "github.com/else": ["github.com/else/x"],
+                "github.com/org": ["github.com/org/other/sub", "github.com/org/repo"],
+                "std": ["fmt"],
+            })
+        );
+        assert_eq!(manifest["lib"], serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_module_prefix() {
+        assert_eq!(module_prefix("gno.land/p/demo/avl"), "gno.land/p");
+        assert_eq!(module_prefix("example.com"), "example.com");
+        assert_eq!(module_prefix("encoding/json"), "std");
+    }
+}
@@ -0,0 +1,138 @@
+use super::{DependencyAnalyzer, Map, Set};
+
+impl DependencyAnalyzer {
+    /// Formats the import graph as a Mermaid `graph TD` flowchart, for
+    /// embedding in Markdown.
+    ///
+    /// Every package becomes a node labelled with its name and coupling
+    /// score, and every internal import an edge from importer to imported
+    /// package. Mermaid only accepts plain identifiers as node IDs, so IDs
+    /// are the package names with every other character replaced by `_`,
+    /// numbered when two names map to the same ID. Nodes and edges are
+    /// sorted by name. With `legend`, a `legend` subgraph explaining the
+    /// labels and arrows follows the edges.
+    pub(super) fn format_mermaid_output(&self, legend: bool) -> String {
+        let mut names: Vec<&str> = self.packages.keys().map(String::as_str).collect();
+        names.sort();
+
+        let mut ids: Map<&str, String> = Map::default();
+        let mut taken: Set<String> = Set::default();
+        if legend {
+            taken.extend(LEGEND_IDS.iter().map(|id| id.to_string()));
+        }
+        for name in &names {
+            let base = mermaid_id(name);
+            let mut id = base.clone();
+            let mut suffix = 2;
+            while !taken.insert(id.clone()) {
+                id = format!("{}_{}", base, suffix);
+                suffix += 1;
+            }
+            ids.insert(name, id);
+        }
+
+        let mut output = String::from("graph TD\n");
+        for name in &names {
+            let package = &self.packages[*name];
+            let score = if package.isolated {
+                "n/a".to_string()
+            } else {
+                format!("{:.2}", package.coupling_score)
+            };
+            output.push_str(&format!(
+                "    {}[\"{} ({})\"]\n",
+                ids[name],
+                name.replace('"', "#quot;"),
+                score
+            ));
+        }
+
+        for name in &names {
+            for dependency in self.internal_imports(name) {
+                output.push_str(&format!("    {} --> {}\n", ids[name], ids[dependency]));
+            }
+        }
+
+        if legend {
+            output.push_str(MERMAID_LEGEND);
+        }
+        output
+    }
+}
+
+/// Node IDs used by `MERMAID_LEGEND`, kept clear of package node IDs
+const LEGEND_IDS: [&str; 3] = ["legend", "legend_package", "legend_import"];
+
+/// Key to the Mermaid export
+const MERMAID_LEGEND: &str = r#"    subgraph legend [Legend]
+        legend_package["package (instability, n/a if isolated)"]
+        legend_import["imported package"]
+        legend_package -->|imports| legend_import
+    end
+"#;
+
+/// Replaces every character Mermaid doesn't accept in a node ID with `_`
+fn mermaid_id(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::ExportOptions;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_mermaid_sanitizes_node_ids() {
+        let sources = [
+            (
+                "app/app.go",
+                "package app\nimport (\n\"gno.land/p/demo/avl\"\n\"fmt\"\n)",
+            ),
+            ("gno.land/p/demo/avl/avl.go", "package avl"),
+            ("tools/tools.go", "package tools"),
+        ];
+        let mut analyzer = TestRepo::new().files(&sources).analyze();
+        analyzer.calculate_coupling_scores();
+
+        let output = analyzer
+            .export_analysis("mermaid", &Default::default())
+            .unwrap();
+        assert!(output.starts_with("graph TD\n"));
+
+        let lines: Vec<&str> = output.lines().map(str::trim).collect();
+        assert!(lines.contains(&"app[\"app (1.00)\"]"));
+        assert!(lines.contains(&"tools[\"tools (n/a)\"]"));
+        assert!(lines.contains(&"app --> avl"));
+        assert_eq!(lines.iter().filter(|l| l.contains(" --> ")).count(), 1);
+
+        assert_eq!(mermaid_id("gno.land/p/demo"), "gno_land_p_demo");
+    }
+
+    #[test]
+    fn test_mermaid_legend_only_when_requested() {
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("app/app.go", "package app\nimport \"legend\""),
+                ("legend/legend.go", "package legend"),
+            ])
+            .analyze();
+        analyzer.calculate_coupling_scores();
+
+        let output = analyzer
+            .export_analysis("mermaid", &Default::default())
+            .unwrap();
+        assert!(!output.contains("subgraph"));
+        assert!(output.contains("    app --> legend\n"));
+
+        let options = ExportOptions {
+            legend: true,
+            ..Default::default()
+        };
+        let output = analyzer.export_analysis("mermaid", &options).unwrap();
+        assert!(output.contains("    app --> legend_2\n"));
+        assert!(output.ends_with(MERMAID_LEGEND));
+    }
+}
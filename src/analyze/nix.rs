@@ -0,0 +1,109 @@
+use super::DependencyAnalyzer;
+
+impl DependencyAnalyzer {
+    /// Formats the deployment order as a Nix attribute set.
+    ///
+    /// The result is a function taking `mkDerivation` and returning a
+    /// recursive attrset with one derivation per package, in deployment
+    /// order. Each derivation lists the packages it imports as `buildInputs`,
+    /// so Nix builds dependencies first. Package names are turned into valid
+    /// Nix identifiers by `nix_identifier`.
+    ///
+    /// # Warning
+    ///
+    /// Packages on an import cycle reference each other, which Nix rejects
+    /// as infinite recursion when the derivations are evaluated.
+    pub(super) fn format_nix_output(&self) -> String {
+        let mut output = String::from("{ mkDerivation }:\n\nrec {\n");
+        for package in self.generate_deployment_order() {
+            let inputs: Vec<String> = self
+                .internal_imports(&package.key)
+                .into_iter()
+                .filter(|dependency| *dependency != package.key)
+                .map(nix_identifier)
+                .collect();
+
+            output.push_str(&format!(
+                "  {} = mkDerivation {{\n",
+                nix_identifier(&package.key)
+            ));
+            output.push_str(&format!(
+                "    pname = \"{}\";\n",
+                escape_nix_string(&package.key)
+            ));
+            if inputs.is_empty() {
+                output.push_str("    buildInputs = [ ];\n");
+            } else {
+                output.push_str(&format!("    buildInputs = [ {} ];\n", inputs.join(" ")));
+            }
+            output.push_str("  };\n");
+        }
+        output.push_str("}\n");
+        output
+    }
+}
+
+/// Turns a package name into a Nix identifier.
+///
+/// Identifiers may only contain letters, digits, `_`, `-` and `'`, and must
+/// start with a letter or `_`; any other character (such as `/` or `.`)
+/// becomes `_`.
+fn nix_identifier(name: &str) -> String {
+    let mut identifier: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '\'') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !identifier.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        identifier.insert(0, '_');
+    }
+    identifier
+}
+
+/// Escapes characters with a special meaning inside a Nix string
+fn escape_nix_string(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("${", "\\${")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_dependency_is_listed_in_build_inputs() {
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("a/a.go", "package a\nimport \"b\""),
+                ("b/b.go", "package b"),
+            ])
+            .analyze();
+
+        let output = analyzer
+            .export_analysis("nix", &Default::default())
+            .unwrap();
+        assert!(output.starts_with("{ mkDerivation }:\n"));
+        assert!(
+            output.contains("  a = mkDerivation {\n    pname = \"a\";\n    buildInputs = [ b ];\n")
+        );
+        assert!(
+            output.contains("  b = mkDerivation {\n    pname = \"b\";\n    buildInputs = [ ];\n")
+        );
+        assert!(output.find("  b = ").unwrap() < output.find("  a = ").unwrap());
+    }
+
+    #[test]
+    fn test_identifiers_are_escaped() {
+        assert_eq!(nix_identifier("gno.land/p/demo"), "gno_land_p_demo");
+        assert_eq!(nix_identifier("9lives"), "_9lives");
+        assert_eq!(nix_identifier("avl-tree"), "avl-tree");
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::path::Path;
+
+use super::{AnalyzeError, DependencyAnalyzer};
+
+/// A committed deployment plan: waves of packages deployed in sequence.
+///
+/// A plan is either the `gha-matrix` JSON emitted by `generate --format
+/// gha-matrix`, whose waves may be deployed in parallel, or a text file
+/// listing one package per line in deployment order, where each line is a
+/// wave of its own. Blank lines and `#` comments are ignored in text plans.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeploymentPlan {
+    waves: Vec<Vec<String>>,
+}
+
+/// Shape of a `gha-matrix` plan
+#[derive(serde::Deserialize)]
+struct MatrixPlan {
+    wave: Vec<Vec<String>>,
+}
+
+impl DeploymentPlan {
+    /// Reads a plan from a file
+    pub fn load(path: &Path) -> Result<Self, AnalyzeError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses a plan from a `gha-matrix` JSON document or a text file
+    pub fn parse(contents: &str) -> Result<Self, AnalyzeError> {
+        if contents.trim_start().starts_with('{') {
+            let plan: MatrixPlan = serde_json::from_str(contents)
+                .map_err(|err| AnalyzeError::Parse(format!("invalid plan: {}", err)))?;
+            return Ok(Self { waves: plan.wave });
+        }
+
+        let waves = contents
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+            .map(|name| vec![name.to_string()])
+            .collect();
+        Ok(Self { waves })
+    }
+
+    /// Index of the wave deploying `name`, if the plan lists it
+    fn wave_of(&self, name: &str) -> Option<usize> {
+        self.waves
+            .iter()
+            .position(|wave| wave.iter().any(|package| package == name))
+    }
+}
+
+/// An import the plan doesn't deploy in time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanViolation {
+    /// `dependency` is deployed in the same wave as `package` or after it
+    OutOfOrder { package: String, dependency: String },
+    /// `dependency` isn't part of the plan at all
+    MissingDependency { package: String, dependency: String },
+}
+
+impl fmt::Display for PlanViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanViolation::OutOfOrder {
+                package,
+                dependency,
+            } => write!(
+                f,
+                "{} imports {} but the plan doesn't deploy {} first",
+                package, dependency, dependency
+            ),
+            PlanViolation::MissingDependency {
+                package,
+                dependency,
+            } => write!(
+                f,
+                "{} imports {} but the plan doesn't deploy {}",
+                package, dependency, dependency
+            ),
+        }
+    }
+}
+
+impl DependencyAnalyzer {
+    /// Checks that a committed plan still deploys every package after the
+    /// packages it imports.
+    ///
+    /// Packages of the plan are checked wave by wave and by name within a
+    /// wave; planned packages that no longer exist are ignored.
+    ///
+    /// # Returns
+    ///
+    /// * The violations in plan order, empty if the plan is still valid
+    pub fn plan_violations(&self, plan: &DeploymentPlan) -> Vec<PlanViolation> {
+        let mut violations = Vec::new();
+        for (index, wave) in plan.waves.iter().enumerate() {
+            let mut wave: Vec<&String> = wave.iter().collect();
+            wave.sort();
+            for package in wave {
+                for dependency in self.internal_imports(package) {
+                    if dependency == package {
+                        continue;
+                    }
+                    let (package, dependency) = (package.clone(), dependency.to_string());
+                    match plan.wave_of(&dependency) {
+                        Some(wave) if wave < index => {}
+                        Some(_) => violations.push(PlanViolation::OutOfOrder {
+                            package,
+                            dependency,
+                        }),
+                        None => violations.push(PlanViolation::MissingDependency {
+                            package,
+                            dependency,
+                        }),
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_text_and_matrix_plans() {
+        let text = DeploymentPlan::parse("# base first\nlib\n\napp # last\n").unwrap();
+        assert_eq!(text.waves, vec![vec!["lib"], vec!["app"]]);
+
+        let matrix = DeploymentPlan::parse(r#"{"wave": [["lib", "util"], ["app"]]}"#).unwrap();
+        assert_eq!(matrix.waves, vec![vec!["lib", "util"], vec!["app"]]);
+        assert_eq!(matrix.wave_of("util"), Some(0));
+        assert_eq!(matrix.wave_of("missing"), None);
+
+        assert!(DeploymentPlan::parse("{\"wave\": 1}").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::AnalyzeError;
+
+/// Manual deployment priorities, read from a priority file.
+///
+/// Each non-blank line holds a package name and an integer weight separated
+/// by whitespace; `#` starts a comment. Packages that aren't listed have
+/// weight 0, and names that don't match an analyzed package are ignored.
+///
+/// ```text
+/// # deploy the registry as early as possible
+/// registry 10
+/// dashboard -5
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Priorities {
+    weights: BTreeMap<String, i64>,
+}
+
+impl Priorities {
+    /// Reads priorities from a priority file
+    pub fn load(path: &Path) -> Result<Self, AnalyzeError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses priorities from the contents of a priority file
+    pub fn parse(contents: &str) -> Result<Self, AnalyzeError> {
+        let mut weights = BTreeMap::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = || {
+                AnalyzeError::Parse(format!(
+                    "line {}: expected `PACKAGE WEIGHT`, found `{}`",
+                    number + 1,
+                    line
+                ))
+            };
+            let mut fields = line.split_whitespace();
+            let (Some(name), Some(weight), None) = (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid());
+            };
+            let weight: i64 = weight.parse().map_err(|_| invalid())?;
+            weights.insert(name.to_string(), weight);
+        }
+
+        Ok(Self { weights })
+    }
+
+    /// Weight of a package, 0 if it isn't listed
+    pub fn weight(&self, name: &str) -> i64 {
+        self.weights.get(name).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_priorities() {
+        let priorities =
+            Priorities::parse("# ops\nregistry 10\n\n  dashboard  -5 # last\n").unwrap();
+        assert_eq!(priorities.weight("registry"), 10);
+        assert_eq!(priorities.weight("dashboard"), -5);
+        assert_eq!(priorities.weight("other"), 0);
+
+        assert!(Priorities::parse("registry").is_err());
+        assert!(Priorities::parse("registry high").is_err());
+        assert!(Priorities::parse("registry 1 2").is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How far `analyze_directory_with_progress` got through the source files
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// Files parsed so far
+    pub done: usize,
+    /// Source files found by the walk
+    pub total: usize,
+    /// Lines of all files parsed so far
+    pub lines: usize,
+    /// Time since parsing started
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Estimated time until every file is parsed, from the average time per
+    /// file observed so far; `None` before the first file is done
+    pub fn eta(&self) -> Option<Duration> {
+        if self.done == 0 {
+            return None;
+        }
+        let remaining = self.total.saturating_sub(self.done) as u32;
+        Some(self.elapsed / self.done as u32 * remaining)
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} files, {} lines",
+            self.done, self.total, self.lines
+        )?;
+        match self.eta() {
+            Some(eta) => write!(f, ", ETA {}s", eta.as_secs()),
+            None => write!(f, ", ETA unknown"),
+        }
+    }
+}
+
+/// Counts parsed files across parser threads
+#[derive(Debug)]
+pub(super) struct ProgressTracker {
+    started: Instant,
+    state: Mutex<Progress>,
+}
+
+impl ProgressTracker {
+    pub(super) fn new(total: usize) -> Self {
+        Self {
+            started: Instant::now(),
+            state: Mutex::new(Progress {
+                done: 0,
+                total,
+                lines: 0,
+                elapsed: Duration::ZERO,
+            }),
+        }
+    }
+
+    /// Records a parsed file of `lines` lines
+    pub(super) fn tick(&self, lines: usize) -> Progress {
+        self.tick_at(lines, self.started.elapsed())
+    }
+
+    /// Records a parsed file of `lines` lines, `elapsed` after parsing started
+    fn tick_at(&self, lines: usize, elapsed: Duration) -> Progress {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.done += 1;
+        state.lines += lines;
+        state.elapsed = elapsed;
+        *state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eta_follows_average_file_time() {
+        let tracker = ProgressTracker::new(10);
+
+        let first = tracker.tick_at(100, Duration::from_secs(2));
+        assert_eq!(first.eta(), Some(Duration::from_secs(18)));
+
+        // Files got slower: 4 files in 20s leaves 6 files at 5s each
+        for elapsed in [5, 12] {
+            tracker.tick_at(10, Duration::from_secs(elapsed));
+        }
+        let fourth = tracker.tick_at(10, Duration::from_secs(20));
+        assert_eq!(fourth.eta(), Some(Duration::from_secs(30)));
+        assert_eq!(fourth.to_string(), "4/10 files, 130 lines, ETA 30s");
+
+        let pending = Progress {
+            done: 0,
+            total: 10,
+            lines: 0,
+            elapsed: Duration::ZERO,
+        };
+        assert_eq!(pending.eta(), None);
+    }
+}
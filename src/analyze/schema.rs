@@ -0,0 +1,58 @@
+use schemars::JsonSchema;
+
+use super::{PackageAnalysis, ReportMeta, ReportSummary};
+
+/// Shapes of the `json` export: the bare package list, or the list along
+/// with the report metadata and summary when `ExportOptions::meta` is set
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+#[serde(untagged)]
+enum Report {
+    Packages(Vec<PackageAnalysis>),
+    WithMeta {
+        meta: ReportMeta,
+        summary: ReportSummary,
+        packages: Vec<PackageAnalysis>,
+    },
+}
+
+/// Returns the JSON Schema of the `json` export format, for validating
+/// reports in downstream tools.
+///
+/// The schema accepts both shapes of the report: the array of packages,
+/// and the `{ "meta": ..., "summary": ..., "packages": [...] }` object
+/// written when report metadata is included, as the `analyze` command does.
+pub fn report_schema() -> serde_json::Value {
+    schemars::schema_for!(Report).to_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::ExportOptions;
+    use crate::analyze::fixture::fixture_analyzer;
+
+    #[test]
+    fn test_schema_validates_reports() {
+        let schema = report_schema();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        let analyzer = fixture_analyzer();
+
+        let with_meta = ExportOptions {
+            detailed: true,
+            meta: Some(ReportMeta::new(
+                vec!["/fixture".into()],
+                serde_json::json!({ "detailed": true }),
+            )),
+            ..Default::default()
+        };
+        for options in [ExportOptions::default(), with_meta] {
+            let output = analyzer.export_analysis("json", &options).unwrap();
+            let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+            assert!(validator.is_valid(&report), "{}", output);
+        }
+
+        let broken = serde_json::json!([{ "name": "app", "coupling_score": "high" }]);
+        assert!(!validator.is_valid(&broken));
+    }
+}
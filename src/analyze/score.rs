@@ -0,0 +1,173 @@
+use evalexpr::{ContextWithMutableVariables, HashMapContext, Node, Value};
+
+use super::{AnalyzeError, DependencyAnalyzer, DetailedMetrics, PackageAnalysis};
+
+/// Variables available to a score formula
+const VARIABLES: [&str; 7] = ["I", "A", "D", "Ca", "Ce", "loc", "impact"];
+
+/// A user-supplied arithmetic expression over package metrics, used to rank
+/// packages by a custom composite score.
+///
+/// Available variables:
+/// - `I`: instability
+/// - `A`: abstractness
+/// - `D`: distance from the main sequence
+/// - `Ca`: afferent coupling
+/// - `Ce`: efferent coupling
+/// - `loc`: lines of code
+/// - `impact`: number of packages depending on the package, directly or transitively
+#[derive(Debug, Clone)]
+pub struct ScoreFormula {
+    expression: String,
+    tree: Node,
+}
+
+impl ScoreFormula {
+    /// Parses a formula such as `Ca + Ce` or `I * loc`.
+    ///
+    /// Fails if the expression is malformed or references an unknown variable.
+    pub fn parse(expression: &str) -> Result<Self, AnalyzeError> {
+        let tree = evalexpr::build_operator_tree(expression)
+            .map_err(|e| AnalyzeError::InvalidFormula(e.to_string()))?;
+
+        if let Some(unknown) = tree
+            .iter_variable_identifiers()
+            .find(|name| !VARIABLES.contains(name))
+        {
+            return Err(AnalyzeError::InvalidFormula(format!(
+                "unknown variable `{}` (expected one of {})",
+                unknown,
+                VARIABLES.join(", ")
+            )));
+        }
+
+        Ok(Self {
+            expression: expression.to_string(),
+            tree,
+        })
+    }
+
+    /// Returns the formula as written
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Evaluates the formula for one package
+    fn evaluate(&self, metrics: &DetailedMetrics, impact: usize) -> Result<f64, AnalyzeError> {
+        let values = [
+            ("I", metrics.instability),
+            ("A", metrics.abstractness),
+            ("D", metrics.distance),
+            ("Ca", metrics.afferent_coupling as f64),
+            ("Ce", metrics.efferent_coupling as f64),
+            ("loc", metrics.lines_of_code as f64),
+            ("impact", impact as f64),
+        ];
+
+        let mut context = HashMapContext::new();
+        for (name, value) in values {
+            context
+                .set_value(name.to_string(), Value::Float(value))
+                .map_err(|e| AnalyzeError::InvalidFormula(e.to_string()))?;
+        }
+
+        self.tree
+            .eval_number_with_context(&context)
+            .map_err(|e| AnalyzeError::InvalidFormula(e.to_string()))
+    }
+}
+
+impl DependencyAnalyzer {
+    /// Scores each result with the formula and re-sorts the results by
+    /// descending score, breaking ties by name
+    pub(super) fn apply_score_formula(
+        &self,
+        formula: &ScoreFormula,
+        results: &mut [PackageAnalysis],
+    ) -> Result<(), AnalyzeError> {
+        let impact = self.impact_counts();
+
+        for result in results.iter_mut() {
+            let package_impact = impact.get(result.key.as_str()).copied().unwrap_or(0);
+            result.score = Some(formula.evaluate(&result.metrics, package_impact)?);
+        }
+
+        results.sort_by(|a, b| {
+            b.score
+                .unwrap_or(0.0)
+                .total_cmp(&a.score.unwrap_or(0.0))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::ExportOptions;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_rejects_unknown_variables() {
+        assert!(ScoreFormula::parse("Ca + Ce").is_ok());
+        assert!(ScoreFormula::parse("I * loc + impact - A - D").is_ok());
+        assert!(ScoreFormula::parse("Ca + churn").is_err());
+        assert!(ScoreFormula::parse("(Ca + Ce").is_err());
+    }
+
+    #[test]
+    fn test_ranks_by_total_coupling() {
+        // hub is imported by a, b and c and imports util: Ca + Ce = 4
+        let mut analyzer = TestRepo::new()
+            .files(&[
+                ("a/a.go", "package a\nimport \"hub\""),
+                ("b/b.go", "package b\nimport \"hub\""),
+                ("c/c.go", "package c\nimport (\n\"hub\"\n\"util\"\n)"),
+                ("hub/hub.go", "package hub\nimport \"util\""),
+                ("util/util.go", "package util"),
+            ])
+            .analyze();
+        analyzer.calculate_coupling_scores();
+
+        let options = ExportOptions {
+            score_formula: Some(ScoreFormula::parse("Ca + Ce").unwrap()),
+            ..Default::default()
+        };
+        let output = analyzer.export_analysis("json", &options).unwrap();
+        let results: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+        let ranking: Vec<(&str, f64)> = results
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| (r["name"].as_str().unwrap(), r["score"].as_f64().unwrap()))
+            .collect();
+        assert_eq!(
+            ranking,
+            vec![
+                ("hub", 4.0),
+                ("c", 2.0),
+                ("util", 2.0),
+                ("a", 1.0),
+                ("b", 1.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_impact_counts_transitive_dependents() {
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("A/A.go", "package A\nimport \"B\""),
+                ("B/B.go", "package B\nimport \"C\""),
+                ("C/C.go", "package C"),
+            ])
+            .analyze();
+
+        let impact = analyzer.impact_counts();
+        assert_eq!(impact["A"], 0);
+        assert_eq!(impact["B"], 1);
+        assert_eq!(impact["C"], 2);
+    }
+}
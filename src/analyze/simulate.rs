@@ -0,0 +1,161 @@
+use std::fmt::Write;
+
+use super::DependencyAnalyzer;
+
+/// What deleting a package would do to the rest of the project
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct RemovalImpact {
+    /// The package being removed
+    pub package: String,
+    /// Packages that import the removed package and would be left with a
+    /// dangling import, sorted by name
+    pub broken: Vec<String>,
+    /// Mean instability of the current project
+    pub average_instability_before: f64,
+    /// Mean instability of the remaining packages after the removal
+    pub average_instability_after: f64,
+    /// Number of import cycles in the current project
+    pub cycles_before: usize,
+    /// Number of import cycles left after the removal
+    pub cycles_after: usize,
+}
+
+impl RemovalImpact {
+    /// Whether the package can be deleted without breaking any other package
+    pub fn is_safe(&self) -> bool {
+        self.broken.is_empty()
+    }
+
+    /// Renders the impact as a changelog-style Markdown report
+    pub fn report(&self) -> String {
+        let mut output = format!("## Remove `{}`\n\n", self.package);
+
+        output.push_str("### Broken\n\n");
+        if self.broken.is_empty() {
+            output.push_str("- Nothing, no package imports it\n");
+        }
+        for name in &self.broken {
+            let _ = writeln!(output, "- {} (dangling import of {})", name, self.package);
+        }
+
+        output.push_str("\n### Metrics\n\n");
+        let _ = writeln!(
+            output,
+            "- Average instability: {:.2} -> {:.2} ({:+.2})",
+            self.average_instability_before,
+            self.average_instability_after,
+            self.average_instability_after - self.average_instability_before
+        );
+        let _ = writeln!(
+            output,
+            "- Cycles: {} -> {} ({:+})",
+            self.cycles_before,
+            self.cycles_after,
+            self.cycles_after as i64 - self.cycles_before as i64
+        );
+        output
+    }
+}
+
+impl DependencyAnalyzer {
+    /// Simulates deleting a package without touching the analyzed state.
+    ///
+    /// Every package importing `name` ends up with a dangling import; those
+    /// imports still count toward the importer's efferent coupling, while the
+    /// removed package no longer contributes to the afferent coupling of the
+    /// packages it imported.
+    ///
+    /// # Returns
+    ///
+    /// * `None` if no package called `name` was analyzed
+    pub fn simulate_removal(&self, name: &str) -> Option<RemovalImpact> {
+        if !self.packages.contains_key(name) {
+            return None;
+        }
+
+        let counts = self.coupling_counts();
+        let removed_imports = self.internal_imports(name);
+        let instability = |afferent: usize, efferent: usize| {
+            if afferent + efferent > 0 {
+                efferent as f64 / (afferent + efferent) as f64
+            } else {
+                0.0
+            }
+        };
+
+        let before: Vec<f64> = counts
+            .values()
+            .map(|c| instability(c.afferent, c.efferent))
+            .collect();
+        let after: Vec<f64> = counts
+            .iter()
+            .filter(|(package, _)| package.as_str() != name)
+            .map(|(package, c)| {
+                let lost = usize::from(removed_imports.contains(&package.as_str()));
+                instability(c.afferent.saturating_sub(lost), c.efferent)
+            })
+            .collect();
+
+        let mut broken: Vec<String> = self
+            .packages
+            .keys()
+            .filter(|package| package.as_str() != name)
+            .filter(|package| self.internal_imports(package).contains(&name))
+            .cloned()
+            .collect();
+        broken.sort();
+
+        Some(RemovalImpact {
+            package: name.to_string(),
+            broken,
+            average_instability_before: mean(&before),
+            average_instability_after: mean(&after),
+            cycles_before: self.cycle_count(),
+            cycles_after: self.count_cycles(Some(name)),
+        })
+    }
+}
+
+/// Arithmetic mean, 0.0 for an empty slice
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_removal_lists_dependents_as_broken() {
+        // A -> C, B -> C, C -> D, D -> C (cycle C <-> D)
+        let analyzer = TestRepo::new()
+            .files(&[
+                ("A/A.go", "package A\nimport \"C\""),
+                ("B/B.go", "package B\nimport \"C\""),
+                ("C/C.go", "package C\nimport \"D\""),
+                ("D/D.go", "package D\nimport \"C\""),
+            ])
+            .analyze();
+
+        let impact = analyzer.simulate_removal("C").unwrap();
+        assert_eq!(impact.broken, vec!["A", "B", "D"]);
+        assert!(!impact.is_safe());
+        assert_eq!((impact.cycles_before, impact.cycles_after), (1, 0));
+
+        // before: A=1, B=1, C=1/4, D=1/2; after: A=1, B=1, D=1/1
+        assert!((impact.average_instability_before - 2.75 / 4.0).abs() < 1e-9);
+        assert!((impact.average_instability_after - 1.0).abs() < 1e-9);
+
+        let report = impact.report();
+        assert!(report.contains("- A (dangling import of C)\n"));
+        assert!(report.contains("- Cycles: 1 -> 0 (-1)\n"));
+
+        let leaf = analyzer.simulate_removal("A").unwrap();
+        assert!(leaf.is_safe());
+        assert!(analyzer.simulate_removal("missing").is_none());
+    }
+}
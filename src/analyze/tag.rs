@@ -0,0 +1,129 @@
+use std::fmt;
+use std::path::Component;
+
+use super::DependencyAnalyzer;
+
+/// Tags packages whose directory matches a glob pattern, written
+/// `PATTERN=TAG`.
+///
+/// The pattern is matched against every trailing part of the package's
+/// directory, so `cmd/**=binary` tags the package in `/repo/cmd/tool` and
+/// `internal=internal` tags the one in `/repo/internal`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagRule {
+    pattern: glob::Pattern,
+    tag: String,
+}
+
+impl std::str::FromStr for TagRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pattern, tag) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected `PATTERN=TAG`, found `{}`", s))?;
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(format!("missing tag in `{}`", s));
+        }
+        let pattern = glob::Pattern::new(pattern.trim())
+            .map_err(|e| format!("invalid pattern in `{}`: {}", s, e))?;
+        Ok(Self {
+            pattern,
+            tag: tag.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for TagRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.pattern, self.tag)
+    }
+}
+
+impl DependencyAnalyzer {
+    /// Sets the rules deriving package tags from package directories
+    pub fn set_tag_rules(&mut self, rules: Vec<TagRule>) {
+        self.tag_rules = rules;
+    }
+
+    /// Tags of a package from the rules matching its directory, sorted
+    pub(super) fn package_tags(&self, name: &str) -> Vec<String> {
+        if self.tag_rules.is_empty() {
+            return Vec::new();
+        }
+        let Some(dir) = self.package_directories().get(name).copied() else {
+            return Vec::new();
+        };
+
+        let segments: Vec<&str> = dir
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(segment) => segment.to_str(),
+                _ => None,
+            })
+            .collect();
+        let suffixes: Vec<String> = (0..segments.len())
+            .map(|start| segments[start..].join("/"))
+            .collect();
+
+        let mut tags: Vec<String> = self
+            .tag_rules
+            .iter()
+            .filter(|rule| suffixes.iter().any(|suffix| rule.pattern.matches(suffix)))
+            .map(|rule| rule.tag.clone())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::ExportOptions;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_tags_from_directories_filter_report() {
+        let sources = [
+            ("repo/cmd/tool/main.go", "package main\nimport \"auth\""),
+            ("repo/internal/auth/auth.go", "package auth"),
+            ("repo/pkg/api/api.go", "package api\nimport \"auth\""),
+        ];
+        let mut analyzer = TestRepo::new().files(&sources).analyze();
+        analyzer.set_tag_rules(vec![
+            "cmd/**=binary".parse().unwrap(),
+            "internal/**=internal".parse().unwrap(),
+            "*/auth=security".parse().unwrap(),
+        ]);
+
+        assert_eq!(analyzer.package_tags("main"), vec!["binary"]);
+        assert_eq!(analyzer.package_tags("auth"), vec!["internal", "security"]);
+        assert!(analyzer.package_tags("api").is_empty());
+
+        let options = ExportOptions {
+            tags: vec!["internal".to_string()],
+            ..Default::default()
+        };
+        let output = analyzer.export_analysis("json", &options).unwrap();
+        let results: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let results = results.as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["name"], "auth");
+        assert_eq!(
+            results[0]["tags"],
+            serde_json::json!(["internal", "security"])
+        );
+    }
+
+    #[test]
+    fn test_parse_tag_rule() {
+        let rule: TagRule = "cmd/**=binary".parse().unwrap();
+        assert_eq!(rule.to_string(), "cmd/**=binary");
+        assert!("cmd/**".parse::<TagRule>().is_err());
+        assert!("cmd/**=".parse::<TagRule>().is_err());
+        assert!("[=binary".parse::<TagRule>().is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+
+use super::DependencyAnalyzer;
+
+/// An import of an `internal` package from outside the tree it is
+/// restricted to
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InternalViolation {
+    /// The importing package
+    pub from: String,
+    /// The imported internal package
+    pub to: String,
+    /// Import path of the directory whose tree may import `to`
+    pub allowed_root: String,
+}
+
+impl fmt::Display for InternalViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} imports {}, which is internal to {}",
+            self.from, self.to, self.allowed_root
+        )
+    }
+}
+
+impl DependencyAnalyzer {
+    /// Finds imports that break Go's rule for `internal` directories: a
+    /// package below `a/b/internal` may only be imported from `a/b` and
+    /// the packages below it.
+    ///
+    /// Both sides are compared by the import paths of the analyzed
+    /// packages, so imports of packages outside the analyzed project
+    /// aren't checked. Like the Go toolchain, the last `internal` element
+    /// of a path decides, and a top-level `internal` directory is open to
+    /// the whole project.
+    ///
+    /// # Returns
+    ///
+    /// * Violations sorted by importing, then imported package
+    pub fn internal_violations(&self) -> Vec<InternalViolation> {
+        let mut violations: Vec<InternalViolation> = self
+            .packages
+            .values()
+            .flat_map(|package| {
+                self.internal_imports(&package.key)
+                    .into_iter()
+                    .filter_map(|dependency| {
+                        let root = internal_root(&self.packages[dependency].import_path)?;
+                        let allowed = root.is_empty()
+                            || package.import_path == root
+                            || package
+                                .import_path
+                                .strip_prefix(root)
+                                .is_some_and(|rest| rest.starts_with('/'));
+                        (!allowed).then(|| InternalViolation {
+                            from: package.key.clone(),
+                            to: dependency.to_string(),
+                            allowed_root: root.to_string(),
+                        })
+                    })
+            })
+            .collect();
+
+        violations.sort();
+        violations
+    }
+}
+
+/// Returns the import path of the directory containing the last `internal`
+/// element of `import_path`, empty for a top-level `internal` directory,
+/// or `None` if the path has no such element. Mirrors the checks of
+/// `go build`.
+fn internal_root(import_path: &str) -> Option<&str> {
+    if let Some(root) = import_path.strip_suffix("/internal") {
+        Some(root)
+    } else if let Some(index) = import_path.rfind("/internal/") {
+        Some(&import_path[..index])
+    } else if import_path == "internal" || import_path.starts_with("internal/") {
+        Some("")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::fixture::TestRepo;
+
+    #[test]
+    fn test_import_of_foreign_internal_package_is_flagged() {
+        let repo = TestRepo::new()
+            .package("app", &["app/internal/db"])
+            .package("cmd", &["app/internal/db"])
+            .file("app/internal/db/db.go", "package db")
+            .file(
+                "app/server/server.go",
+                "package server\nimport \"app/internal/db\"",
+            );
+        let analyzer = repo.analyze();
+
+        assert_eq!(
+            analyzer.internal_violations(),
+            vec![InternalViolation {
+                from: "cmd".to_string(),
+                to: "db".to_string(),
+                allowed_root: "app".to_string(),
+            }]
+        );
+        assert_eq!(
+            analyzer.internal_violations()[0].to_string(),
+            "cmd imports db, which is internal to app"
+        );
+    }
+
+    #[test]
+    fn test_internal_root() {
+        assert_eq!(internal_root("a/b/internal/c"), Some("a/b"));
+        assert_eq!(internal_root("a/b/internal"), Some("a/b"));
+        assert_eq!(
+            internal_root("a/internal/b/internal/c"),
+            Some("a/internal/b")
+        );
+        assert_eq!(internal_root("internal/c"), Some(""));
+        assert_eq!(internal_root("internal/x/internal/c"), Some("internal/x"));
+        assert_eq!(internal_root("a/internals/c"), None);
+        assert_eq!(internal_root("a/b"), None);
+    }
+}
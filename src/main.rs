@@ -0,0 +1,905 @@
+use clap::{Parser, Subcommand};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::slice;
+
+use deploy::analyze;
+
+/// Number of package pairs listed by --co-dependency
+const CO_DEPENDENCY_LIMIT: usize = 10;
+
+#[derive(Parser)]
+#[command(name = "deploy")]
+#[command(author = "")]
+#[command(version = "1.0")]
+#[command(about = "Analyzes Gno package dependencies and generates ordered code", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+// Parsed once per run, so the size of the Analyze variant doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+enum Commands {
+    /// Analyze dependencies and show coupling scores
+    Analyze {
+        /// Paths to the Go project directories, analyzed as one project, or
+        /// `-` to read a single Go file from stdin
+        #[arg(value_name = "PROJECT_PATH", required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Output format (text, json, opml, communities, hierarchy, dgml, nix, flamegraph, deps-manifest, d3, dot, mermaid, csv, yaml, html)
+        ///
+        /// The graph formats (opml, communities, hierarchy, dgml, nix, flamegraph, d3, dot,
+        /// mermaid) always show every package and fail if --hide-leaf-utilities,
+        /// --exclude-package, --min-score, --zone, --tag or --top is given.
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Show detailed metrics for each package
+        #[arg(short, long)]
+        detailed: bool,
+
+        /// Hide packages that only import external packages and have no internal dependents
+        #[arg(long)]
+        hide_leaf_utilities: bool,
+
+        /// Order packages by this metric (instability, api-surface, bus-risk)
+        #[arg(long, default_value = "instability")]
+        sort_by: analyze::SortKey,
+
+        /// Print the deployment order (text or json) instead of the report
+        #[arg(long, conflicts_with = "report")]
+        order: bool,
+
+        /// Print a fan-in or fan-out leaderboard (fanin, fanout) instead of the report
+        #[arg(long, value_name = "KIND")]
+        report: Option<analyze::FanReport>,
+
+        /// Rank packages by an expression over I, A, D, Ca, Ce, loc and impact
+        #[arg(long, value_name = "EXPR")]
+        score_formula: Option<String>,
+
+        /// Only report packages with at least this instability
+        #[arg(long, value_name = "SCORE")]
+        min_score: Option<f64>,
+
+        /// Only report packages in this instability zone (stable, balanced, unstable, isolated)
+        #[arg(long, value_name = "ZONE")]
+        zone: Option<analyze::Zone>,
+
+        /// Only report the first N packages, after filtering and sorting
+        #[arg(long, value_name = "N")]
+        top: Option<usize>,
+
+        /// Append a legend explaining node labels and edge styles to dot and mermaid output
+        #[arg(long)]
+        legend: bool,
+
+        /// Leave packages whose name matches this glob pattern out of the report
+        #[arg(long, value_name = "GLOB")]
+        exclude_package: Vec<String>,
+
+        /// Tag packages whose directory matches PATTERN with TAG (repeatable)
+        #[arg(long, value_name = "PATTERN=TAG")]
+        tag_rule: Vec<analyze::TagRule>,
+
+        /// Only report packages carrying this tag (repeatable)
+        #[arg(long, value_name = "TAG")]
+        tag: Vec<String>,
+
+        /// Skip files and directories matching this glob, relative to the project (repeatable)
+        #[arg(long, value_name = "PATTERN")]
+        exclude: Vec<String>,
+
+        /// Also analyze `*_test.go` files, which are skipped by default
+        #[arg(long)]
+        include_tests: bool,
+
+        /// Only analyze files whose //go:build constraint holds with exactly these tags set
+        #[arg(long, value_name = "TAG", value_delimiter = ',')]
+        build_tags: Option<Vec<String>>,
+
+        /// Only analyze packages whose import path is below this prefix (repeatable)
+        #[arg(long, value_name = "PATH")]
+        prefix: Vec<String>,
+
+        /// Leave imports of packages made up solely of generated files out of instability
+        #[arg(long)]
+        discount_generated_imports: bool,
+
+        /// Leave blank (`import _ "x"`) imports out of the importing package's instability
+        #[arg(long)]
+        exclude_blank_imports: bool,
+
+        /// Weight of imports of packages outside the project in instability, from 0 to 1
+        #[arg(long, value_name = "F", default_value_t = 1.0)]
+        external_weight: f64,
+
+        /// Leave imports between packages of the same import cycle out of instability
+        #[arg(long)]
+        ignore_cycle_edges: bool,
+
+        /// Fail if any package's instability exceeds this
+        #[arg(long, value_name = "F")]
+        max_instability: Option<f64>,
+
+        /// Fail if any package directly imports more than this many packages
+        #[arg(long, value_name = "N")]
+        max_efferent: Option<usize>,
+
+        /// Fail if any package depends on more than this many packages, directly or transitively
+        #[arg(long, value_name = "N")]
+        max_transitive: Option<usize>,
+
+        /// Only count imports of analyzed packages toward --max-efferent
+        #[arg(long)]
+        exclude_stdlib: bool,
+
+        /// Previous `--format json` report to compare against
+        #[arg(long, value_name = "FILE")]
+        baseline: Option<PathBuf>,
+
+        /// Fail if the average instability exceeds the baseline's by more than this
+        #[arg(long, value_name = "DELTA", requires = "baseline")]
+        max_avg_regression: Option<f64>,
+
+        /// Report architectural lint warnings
+        #[arg(long)]
+        lint: bool,
+
+        /// Don't flag packages importing a package from an ancestor directory
+        #[arg(long, requires = "lint")]
+        no_ancestor_imports: bool,
+
+        /// Warn about files importing the same path more than once
+        #[arg(long)]
+        warn_duplicate_imports: bool,
+
+        /// Report imports not allowed by the `FROM -> TO` rules in this file
+        #[arg(long, value_name = "FILE")]
+        arch: Option<PathBuf>,
+
+        /// Fail if any import breaks the --arch rules
+        #[arg(long, requires = "arch")]
+        fail: bool,
+
+        /// Fail if a package imports an `internal` package outside its allowed tree
+        #[arg(long)]
+        check_internal: bool,
+
+        /// Also list the package pairs sharing the most imports
+        #[arg(long)]
+        co_dependency: bool,
+
+        /// Report the location of every syntax error the parser recovered from
+        #[arg(long)]
+        debug_parse: bool,
+
+        /// Print warnings and errors to stderr as JSON objects, one per line
+        #[arg(long)]
+        warnings_json: bool,
+
+        /// Print each package's score and import count on stderr as it is computed
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Show parsed files and an estimated time remaining on stderr
+        #[arg(long)]
+        progress: bool,
+
+        /// Parse every file again and don't write the parse cache, which is on by default and kept in .deploy-cache in each project directory
+        #[arg(long)]
+        no_cache: bool,
+
+        /// Number of threads parsing source files [default: number of logical CPUs]
+        #[arg(short, long, value_name = "N")]
+        jobs: Option<NonZeroUsize>,
+    },
+    /// List discovered packages without computing metrics
+    ListPackages {
+        /// Path to the Go project directory
+        #[arg(value_name = "PROJECT_PATH")]
+        path: PathBuf,
+
+        /// Also list each package's imports
+        #[arg(long)]
+        with_imports: bool,
+
+        /// Only list packages whose name matches this glob pattern
+        #[arg(long, value_name = "GLOB")]
+        filter: Option<String>,
+    },
+    /// Print a JSON index mapping each package to its source files
+    Index {
+        /// Path to the Go project directory
+        #[arg(value_name = "PROJECT_PATH")]
+        path: PathBuf,
+    },
+    /// Explain why a package has its instability score
+    Explain {
+        /// Path to the Go project directory
+        #[arg(value_name = "PROJECT_PATH")]
+        path: PathBuf,
+
+        /// Package to explain
+        #[arg(value_name = "PACKAGE")]
+        package: String,
+    },
+    /// Simulate changes to the project without touching any files
+    Simulate {
+        #[command(subcommand)]
+        action: SimulateAction,
+    },
+    /// Check that a committed deployment plan still respects every import
+    ValidatePlan {
+        /// Path to the Go project directory
+        #[arg(value_name = "PROJECT_PATH")]
+        path: PathBuf,
+
+        /// Plan to check: `generate --format gha-matrix` output or one package per line
+        #[arg(value_name = "PLAN")]
+        plan: PathBuf,
+    },
+    /// Compare two versions of a project: packages, imports and instability
+    Diff {
+        /// Path to the old version of the Go project
+        #[arg(value_name = "OLD_PATH")]
+        old_path: PathBuf,
+
+        /// Path to the new version of the Go project
+        #[arg(value_name = "NEW_PATH")]
+        new_path: PathBuf,
+
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+    /// Print the tree-sitter-go node types and the query used to read sources
+    GrammarInfo,
+
+    /// Print the JSON Schema of `analyze --format json` reports
+    Schema,
+
+    /// Generate code based on dependency order
+    Generate {
+        /// Path to the Go project directory
+        #[arg(value_name = "PROJECT_PATH")]
+        path: PathBuf,
+
+        /// Output directory for generated code
+        #[arg(short, long, value_name = "OUTPUT_DIR")]
+        output: Option<PathBuf>,
+
+        /// Concatenate every package into the single file given by --output
+        #[arg(long, requires = "output")]
+        single_file: bool,
+
+        /// Template to use for code generation
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// Emit the deployment plan in this format instead (gha-matrix, dot-deploy, json, text)
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// File of `PACKAGE WEIGHT` lines; among packages ready at the same
+        /// time, higher weights deploy first
+        #[arg(long, value_name = "FILE")]
+        priority: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SimulateAction {
+    /// Show what would break if a package were deleted
+    Remove {
+        /// Path to the Go project directory
+        #[arg(value_name = "PROJECT_PATH")]
+        path: PathBuf,
+
+        /// Package to remove
+        #[arg(value_name = "PACKAGE")]
+        package: String,
+
+        /// Print a changelog-style report of broken packages and metric changes
+        #[arg(long)]
+        report: bool,
+    },
+}
+
+fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let cli = Cli::parse_from(expand_response_files(std::env::args_os())?);
+
+    match cli.command {
+        Commands::Analyze {
+            paths,
+            format,
+            detailed,
+            hide_leaf_utilities,
+            sort_by,
+            order,
+            report,
+            score_formula,
+            min_score,
+            zone,
+            top,
+            legend,
+            exclude_package,
+            tag_rule,
+            tag,
+            exclude,
+            include_tests,
+            prefix,
+            discount_generated_imports,
+            exclude_blank_imports,
+            external_weight,
+            ignore_cycle_edges,
+            max_instability,
+            max_efferent,
+            max_transitive,
+            exclude_stdlib,
+            baseline,
+            max_avg_regression,
+            lint,
+            no_ancestor_imports,
+            warn_duplicate_imports,
+            arch,
+            fail,
+            check_internal,
+            co_dependency,
+            debug_parse,
+            warnings_json,
+            verbose,
+            progress,
+            no_cache,
+            jobs,
+            build_tags,
+        } => {
+            let diagnostics = Diagnostics {
+                json: warnings_json,
+            };
+
+            let score_formula = score_formula
+                .as_deref()
+                .map(analyze::ScoreFormula::parse)
+                .transpose()?;
+
+            let baseline = baseline
+                .as_deref()
+                .map(analyze::Baseline::load)
+                .transpose()?;
+
+            let arch = arch.as_deref().map(analyze::ArchRules::load).transpose()?;
+
+            let walk = WalkOptions {
+                jobs,
+                progress,
+                exclude: exclude.clone(),
+                include_tests,
+                cache: !no_cache,
+                build_tags: build_tags.clone(),
+            };
+            let mut analyzer = analyze_project(&paths, &walk, diagnostics)?;
+            if debug_parse {
+                for error in analyzer.syntax_errors() {
+                    diagnostics.warn("syntax-error", error);
+                }
+            }
+            analyzer.restrict_to_prefixes(&prefix);
+            let tag_rules: Vec<String> = tag_rule.iter().map(ToString::to_string).collect();
+            analyzer.set_tag_rules(tag_rule);
+            analyzer.set_discount_generated_imports(discount_generated_imports);
+            analyzer.set_exclude_blank_imports(exclude_blank_imports);
+            analyzer.set_external_weight(external_weight)?;
+            analyzer.set_ignore_cycle_edges(ignore_cycle_edges);
+            analyzer.calculate_coupling_scores();
+            if verbose {
+                for package in analyzer.packages() {
+                    eprintln!(
+                        "{}: {:.2} - {} imports",
+                        package.name(),
+                        package.coupling_score(),
+                        package.imports().count()
+                    );
+                }
+            }
+            if sort_by == analyze::SortKey::BusRisk {
+                for path in &paths {
+                    analyzer.load_git_churn(path)?;
+                }
+            }
+
+            warn_cycles(&analyzer, diagnostics);
+            if ignore_cycle_edges {
+                diagnostics.warn(
+                    "cycle-edges",
+                    format!(
+                        "{} import(s) inside cycles left out of instability",
+                        analyzer.ignored_cycle_edges()
+                    ),
+                );
+            }
+
+            let meta = analyze::ReportMeta::new(
+                paths.clone(),
+                serde_json::json!({
+                    "detailed": detailed,
+                    "hide_leaf_utilities": hide_leaf_utilities,
+                    "sort_by": sort_by.to_string(),
+                    "order": order,
+                    "report": report.map(|report| report.to_string()),
+                    "score_formula": score_formula.as_ref().map(|f| f.expression()),
+                    "min_score": min_score,
+                    "zone": zone.map(|zone| zone.to_string()),
+                    "top": top,
+                    "exclude_package": exclude_package,
+                    "tag_rule": tag_rules,
+                    "tag": tag,
+                    "exclude": exclude,
+                    "include_tests": include_tests,
+                    "build_tags": build_tags,
+                    "prefix": prefix,
+                    "discount_generated_imports": discount_generated_imports,
+                    "exclude_blank_imports": exclude_blank_imports,
+                    "external_weight": external_weight,
+                    "ignore_cycle_edges": ignore_cycle_edges,
+                    "exclude_stdlib": exclude_stdlib,
+                    "max_instability": max_instability,
+                    "max_efferent": max_efferent,
+                    "max_transitive": max_transitive,
+                }),
+            );
+
+            // Export and print results
+            let options = analyze::ExportOptions {
+                detailed,
+                hide_leaf_utilities,
+                sort_by,
+                score_formula,
+                min_score,
+                exclude_packages: exclude_package,
+                zone,
+                tags: tag,
+                limit: top,
+                legend,
+                meta: Some(meta),
+            };
+            if order {
+                println!("{}", analyzer.export_deployment_plan(&format)?);
+            } else if let Some(report) = report {
+                for (package, count) in analyzer.fan_report(report) {
+                    println!("{:<40} {}", package, count);
+                }
+            } else {
+                let output = analyzer.export_analysis(&format, &options)?;
+                println!("{}", output);
+            }
+            if format == "text" && !order && report.is_none() {
+                let unresolved = analyzer.unresolved_imports();
+                println!(
+                    "Unresolved imports: {} in {} package(s)",
+                    unresolved.values().map(Vec::len).sum::<usize>(),
+                    unresolved.len()
+                );
+                println!("Modularity: {:.2}", analyzer.modularity());
+            }
+
+            if co_dependency {
+                println!("Most co-dependent packages:");
+                for pair in analyzer.co_dependencies().iter().take(CO_DEPENDENCY_LIMIT) {
+                    println!("  {}", pair);
+                }
+            }
+
+            if lint || warn_duplicate_imports {
+                let lint_options = analyze::LintOptions {
+                    ancestor_imports: lint && !no_ancestor_imports,
+                    alias_consistency: lint,
+                    duplicate_imports: warn_duplicate_imports,
+                };
+                for warning in analyzer.lint(&lint_options) {
+                    let kind = serde_json::to_value(warning.kind)?;
+                    diagnostics.warn(kind.as_str().unwrap_or("lint"), &warning);
+                }
+            }
+
+            if let Some(rules) = &arch {
+                let violations = analyzer.arch_violations(rules);
+                for violation in &violations {
+                    if fail {
+                        diagnostics.error("arch", violation);
+                    } else {
+                        diagnostics.warn("arch", violation);
+                    }
+                }
+                if fail && !violations.is_empty() {
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+
+            if check_internal {
+                let violations = analyzer.internal_violations();
+                for violation in &violations {
+                    diagnostics.error("internal-import", violation);
+                }
+                if !violations.is_empty() {
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+
+            if let Some(max) = max_instability {
+                let violations = analyzer.instability_violations(max);
+                for (package, instability) in &violations {
+                    diagnostics.error(
+                        "max-instability",
+                        format!(
+                            "{} has instability {:.2} (max {})",
+                            package, instability, max
+                        ),
+                    );
+                }
+                if !violations.is_empty() {
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+
+            if let Some(max) = max_efferent {
+                let violations = analyzer.fan_out_violations(max, exclude_stdlib);
+                for (package, fan_out) in &violations {
+                    diagnostics.error(
+                        "max-efferent",
+                        format!("{} imports {} packages (max {})", package, fan_out, max),
+                    );
+                }
+                if !violations.is_empty() {
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+
+            if let Some(max) = max_transitive {
+                let violations = analyzer.transitive_violations(max);
+                for violation in &violations {
+                    diagnostics.error("max-transitive", violation);
+                }
+                if !violations.is_empty() {
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+
+            if let (Some(delta), Some(baseline)) = (max_avg_regression, &baseline) {
+                let regression = analyzer.average_instability_regression(baseline);
+                if regression > delta {
+                    diagnostics.error(
+                        "avg-regression",
+                        format!(
+                            "average instability rose from {:.2} to {:.2} (max regression {})",
+                            baseline.average_instability(),
+                            analyzer.average_instability(),
+                            delta
+                        ),
+                    );
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+        }
+        Commands::ListPackages {
+            path,
+            with_imports,
+            filter,
+        } => {
+            let analyzer = analyze_project(
+                slice::from_ref(&path),
+                &WalkOptions::default(),
+                Diagnostics::default(),
+            )?;
+            print!(
+                "{}",
+                analyzer.list_packages(filter.as_deref(), with_imports)?
+            );
+        }
+        Commands::GrammarInfo => {
+            print!("{}", analyze::grammar_info());
+        }
+        Commands::Schema => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&analyze::report_schema())?
+            );
+        }
+        Commands::Index { path } => {
+            let analyzer = analyze_project(
+                slice::from_ref(&path),
+                &WalkOptions::default(),
+                Diagnostics::default(),
+            )?;
+            let index: BTreeMap<String, Vec<PathBuf>> = analyzer.index().into_iter().collect();
+            println!("{}", serde_json::to_string_pretty(&index)?);
+        }
+        Commands::Explain { path, package } => {
+            let analyzer = analyze_project(
+                slice::from_ref(&path),
+                &WalkOptions::default(),
+                Diagnostics::default(),
+            )?;
+            let explanation = analyzer
+                .explain_instability(&package)
+                .ok_or_else(|| format!("Unknown package: {}", package))?;
+            print!("{}", explanation);
+        }
+        Commands::Simulate {
+            action:
+                SimulateAction::Remove {
+                    path,
+                    package,
+                    report,
+                },
+        } => {
+            let analyzer = analyze_project(
+                slice::from_ref(&path),
+                &WalkOptions::default(),
+                Diagnostics::default(),
+            )?;
+            let impact = analyzer
+                .simulate_removal(&package)
+                .ok_or_else(|| format!("Unknown package: {}", package))?;
+
+            if report {
+                print!("{}", impact.report());
+            } else if impact.is_safe() {
+                println!("{} can be removed safely", package);
+            } else {
+                println!(
+                    "Removing {} would break {} package(s): {}",
+                    package,
+                    impact.broken.len(),
+                    impact.broken.join(", ")
+                );
+            }
+        }
+        Commands::Diff {
+            old_path,
+            new_path,
+            format,
+        } => {
+            let old = analyze_project(
+                slice::from_ref(&old_path),
+                &WalkOptions::default(),
+                Diagnostics::default(),
+            )?;
+            let new = analyze_project(
+                slice::from_ref(&new_path),
+                &WalkOptions::default(),
+                Diagnostics::default(),
+            )?;
+            print!("{}", old.diff(&new).export(&format)?);
+        }
+        Commands::ValidatePlan { path, plan } => {
+            let plan = analyze::DeploymentPlan::load(&plan)?;
+            let analyzer = analyze_project(
+                slice::from_ref(&path),
+                &WalkOptions::default(),
+                Diagnostics::default(),
+            )?;
+            match analyzer.plan_violations(&plan).first() {
+                Some(violation) => {
+                    Diagnostics::default().error("plan", violation);
+                    return Ok(ExitCode::FAILURE);
+                }
+                None => println!("Plan is valid"),
+            }
+        }
+        Commands::Generate {
+            path,
+            output,
+            single_file,
+            template,
+            format,
+            priority,
+        } => {
+            let mut analyzer = analyze_project(
+                slice::from_ref(&path),
+                &WalkOptions::default(),
+                Diagnostics::default(),
+            )?;
+            if let Some(priority) = priority {
+                analyzer.set_priorities(analyze::Priorities::load(&priority)?);
+            }
+            warn_cycles(&analyzer, Diagnostics::default());
+
+            if let Some(format) = format {
+                println!("{}", analyzer.export_deployment_plan(&format)?);
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            if template.is_some() {
+                Diagnostics::default().warn(
+                    "template",
+                    "templates aren't supported yet, using the default layout",
+                );
+            }
+
+            let output = output.unwrap_or_else(|| PathBuf::from("."));
+            let files = analyzer.write_ordered_sources(&output, single_file)?;
+            for file in files {
+                println!("{}", file.display());
+            }
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Pattern of Go test files, which every subcommand skips unless
+/// `WalkOptions::include_tests` is set
+const TEST_FILES: &str = "*_test.go";
+
+/// Analyzes all .go files and gno.mod files in the project directories that
+/// aren't excluded, as a single project, reporting files that had to be
+/// skipped
+///
+/// Files are parsed on a dedicated pool of `walk.jobs` threads if given, or
+/// on rayon's global pool with one thread per logical CPU otherwise. A path
+/// of `-` analyzes a single file read from stdin instead.
+fn analyze_project(
+    paths: &[PathBuf],
+    walk: &WalkOptions,
+    diagnostics: Diagnostics,
+) -> Result<analyze::DependencyAnalyzer, Box<dyn std::error::Error>> {
+    let mut analyzer = analyze::DependencyAnalyzer::new();
+    let mut exclude = walk.exclude.clone();
+    if !walk.include_tests {
+        exclude.push(TEST_FILES.to_string());
+    }
+    analyzer.set_exclude_patterns(&exclude)?;
+    analyzer.set_parse_cache(walk.cache);
+    analyzer.set_build_tags(walk.build_tags.as_deref());
+    let progress = walk.progress;
+    let on_progress = |current: analyze::Progress| {
+        if progress {
+            eprint!("\r\x1b[KParsing {}", current);
+        }
+    };
+    let pool = walk
+        .jobs
+        .map(|jobs| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(jobs.get())
+                .build()
+        })
+        .transpose()?;
+    for path in paths {
+        if path == Path::new("-") {
+            analyzer.analyze_reader(std::io::stdin().lock(), "<stdin>")?;
+            continue;
+        }
+        match &pool {
+            Some(pool) => pool.install(|| {
+                analyzer
+                    .analyze_directory_with_progress(path, on_progress)
+                    .map_err(|e| e.to_string())
+            })?,
+            None => analyzer.analyze_directory_with_progress(path, on_progress)?,
+        }
+        if progress {
+            eprintln!();
+        }
+    }
+
+    let skipped = analyzer.skipped_files();
+    if !skipped.is_empty() {
+        for file in skipped {
+            diagnostics.warn("skipped-file", format!("skipped {}", file));
+        }
+        if !diagnostics.json {
+            eprintln!(
+                "Warning: {} file(s) could not be read or decoded",
+                skipped.len()
+            );
+        }
+    }
+
+    Ok(analyzer)
+}
+
+/// Warns about import cycles, which put their packages at the end of the
+/// deployment order in name order
+fn warn_cycles(analyzer: &analyze::DependencyAnalyzer, diagnostics: Diagnostics) {
+    let cycles = analyzer.find_cycles();
+    if !cycles.is_empty() {
+        let groups: Vec<String> = cycles.iter().map(|cycle| cycle.join(", ")).collect();
+        diagnostics.warn(
+            "cycle",
+            format!(
+                "{} import cycle(s) detected: {{{}}}",
+                cycles.len(),
+                groups.join("}, {")
+            ),
+        );
+    }
+}
+
+/// How `analyze_project` walks and parses the project
+#[derive(Debug, Default)]
+struct WalkOptions {
+    /// Number of parser threads, one per logical CPU if unset
+    jobs: Option<NonZeroUsize>,
+    /// Show parse progress on stderr
+    progress: bool,
+    /// Glob patterns of paths to skip, besides test files
+    exclude: Vec<String>,
+    /// Analyze `*_test.go` files too
+    include_tests: bool,
+    /// Reuse and update the parse cache of each project directory
+    cache: bool,
+    /// Tags `//go:build` constraints are checked against, all files if unset
+    build_tags: Option<Vec<String>>,
+}
+
+/// Reports diagnostics on stderr, as `Warning: ...`/`Error: ...` lines or,
+/// with `--warnings-json`, as one JSON object per line
+#[derive(Debug, Clone, Copy, Default)]
+struct Diagnostics {
+    json: bool,
+}
+
+impl Diagnostics {
+    fn warn(&self, kind: &str, detail: impl std::fmt::Display) {
+        self.emit("warn", kind, detail);
+    }
+
+    fn error(&self, kind: &str, detail: impl std::fmt::Display) {
+        self.emit("error", kind, detail);
+    }
+
+    fn emit(&self, level: &str, kind: &str, detail: impl std::fmt::Display) {
+        if self.json {
+            let diagnostic = serde_json::json!({
+                "level": level,
+                "kind": kind,
+                "detail": detail.to_string(),
+            });
+            eprintln!("{}", diagnostic);
+        } else if level == "error" {
+            eprintln!("Error: {}", detail);
+        } else {
+            eprintln!("Warning: {}", detail);
+        }
+    }
+}
+
+/// Replaces every `@FILE` argument with the arguments listed in that file,
+/// one per line, so long invocations don't hit command-line length limits.
+///
+/// Blank lines are ignored and arguments are taken verbatim, without any
+/// quoting. Response files aren't expanded recursively.
+fn expand_response_files(
+    args: impl IntoIterator<Item = OsString>,
+) -> Result<Vec<OsString>, Box<dyn std::error::Error>> {
+    let mut expanded = Vec::new();
+    for (position, arg) in args.into_iter().enumerate() {
+        let file = arg
+            .to_str()
+            .and_then(|arg| arg.strip_prefix('@'))
+            .filter(|file| position > 0 && !file.is_empty());
+        let Some(file) = file else {
+            expanded.push(arg);
+            continue;
+        };
+
+        let contents = std::fs::read_to_string(file)
+            .map_err(|err| format!("Failed to read response file {}: {}", file, err))?;
+        expanded.extend(
+            contents
+                .lines()
+                .map(str::trim_end)
+                .filter(|line| !line.is_empty())
+                .map(OsString::from),
+        );
+    }
+    Ok(expanded)
+}
@@ -0,0 +1,754 @@
+use std::path::Path;
+use std::process::Command;
+
+fn deploy() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_deploy"))
+}
+
+fn write_file(root: &Path, relative: &str, contents: &str) {
+    let path = root.join(relative);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+
+#[test]
+fn test_max_efferent_fails_on_fan_out() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(
+        root.path(),
+        "app/app.go",
+        "package app\nimport (\n\"lib\"\n\"util\"\n)",
+    );
+    write_file(root.path(), "lib/lib.go", "package lib");
+    write_file(root.path(), "util/util.go", "package util");
+
+    let output = deploy()
+        .args(["analyze", "--max-efferent", "1"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("app"), "stderr: {}", stderr);
+
+    let output = deploy()
+        .args(["analyze", "--max-efferent", "2"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_max_instability_fails_on_unstable_package() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(root.path(), "app/app.go", "package app\nimport \"lib\"");
+    write_file(root.path(), "lib/lib.go", "package lib");
+
+    let output = deploy()
+        .args(["analyze", "--max-instability", "0.8"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("app has instability 1.00 (max 0.8)"),
+        "stderr: {}",
+        stderr
+    );
+
+    let output = deploy().arg("analyze").arg(root.path()).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_max_transitive_fails_on_long_chain() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    let chain = ["a", "b", "c", "d", "e"];
+    for pair in chain.windows(2) {
+        write_file(
+            root.path(),
+            &format!("{0}/{0}.go", pair[0]),
+            &format!("package {}\nimport \"{}\"", pair[0], pair[1]),
+        );
+    }
+    write_file(root.path(), "e/e.go", "package e");
+
+    let output = deploy()
+        .args(["analyze", "--max-transitive", "3"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("a depends on 4 packages transitively (max 3), indirectly c, d, e"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(!stderr.contains("b depends on"), "stderr: {}", stderr);
+
+    let output = deploy()
+        .args(["analyze", "--max-transitive", "4"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_max_avg_regression_fails_on_worsened_project() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    let baseline = root.path().join("baseline.json");
+    std::fs::write(
+        &baseline,
+        r#"[
+            {"name": "app", "coupling_score": 1.0},
+            {"name": "lib", "coupling_score": 0.0}
+        ]"#,
+    )
+    .unwrap();
+
+    // lib now imports an external package, raising the average to 0.75
+    let project = root.path().join("project");
+    write_file(&project, "app/app.go", "package app\nimport \"lib\"");
+    write_file(&project, "lib/lib.go", "package lib\nimport \"fmt\"");
+
+    let output = deploy()
+        .args(["analyze", "--max-avg-regression", "0.1", "--baseline"])
+        .arg(&baseline)
+        .arg(&project)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = deploy()
+        .args(["analyze", "--max-avg-regression", "0.3", "--baseline"])
+        .arg(&baseline)
+        .arg(&project)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_response_file_supplies_arguments() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    let project = root.path().join("project");
+    write_file(&project, "app/app.go", "package app\nimport \"lib\"");
+    write_file(&project, "lib/lib.go", "package lib");
+
+    let targets = root.path().join("targets.txt");
+    std::fs::write(
+        &targets,
+        format!("--format\njson\n\n{}\n", project.display()),
+    )
+    .unwrap();
+
+    let output = deploy()
+        .arg("analyze")
+        .arg(format!("@{}", targets.display()))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"name\": \"app\""), "stdout: {}", stdout);
+    assert!(stdout.contains("\"name\": \"lib\""), "stdout: {}", stdout);
+
+    let output = deploy()
+        .args(["analyze", "@missing-targets.txt"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_warnings_json_reports_cycles() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(root.path(), "x/x.go", "package x\nimport \"y\"");
+    write_file(root.path(), "y/y.go", "package y\nimport \"x\"");
+
+    // --order computes the deployment order, which must not print plain
+    // text warnings of its own
+    for extra in [&[][..], &["--order"]] {
+        let output = deploy()
+            .args(["analyze", "--warnings-json", "--format", "json"])
+            .args(extra)
+            .arg(root.path())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let diagnostics: Vec<serde_json::Value> = stderr
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("stderr line is not JSON"))
+            .collect();
+        let cycle = diagnostics
+            .iter()
+            .find(|d| d["kind"] == "cycle")
+            .expect("no cycle diagnostic");
+        assert_eq!(cycle["level"], "warn");
+        let detail = cycle["detail"].as_str().unwrap();
+        assert!(detail.contains("1 import cycle"), "detail: {}", detail);
+        assert!(detail.ends_with("{x, y}"), "detail: {}", detail);
+    }
+}
+
+#[test]
+fn test_jobs_one_matches_parallel_analysis() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    for i in 0..20 {
+        write_file(
+            root.path(),
+            &format!("p{}/p{}.go", i, i),
+            &format!("package p{}\nimport \"p{}\"", i, (i + 1) % 20),
+        );
+    }
+
+    let packages = |jobs: Option<&str>| {
+        let mut command = deploy();
+        command.arg("analyze");
+        if let Some(jobs) = jobs {
+            command.args(["--jobs", jobs]);
+        }
+        let output = command.arg(root.path()).output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let mut names: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.strip_prefix("Package: "))
+            .map(str::to_string)
+            .collect();
+        names.sort();
+        names
+    };
+
+    let serial = packages(Some("1"));
+    assert_eq!(serial.len(), 20);
+    assert_eq!(serial, packages(Some("4")));
+    assert_eq!(serial, packages(None));
+
+    let output = deploy()
+        .args(["analyze", "--jobs", "0"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_arch_fail_rejects_forbidden_import() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    let rules = root.path().join("arch.txt");
+    std::fs::write(&rules, "api -> db\n").unwrap();
+
+    let project = root.path().join("project");
+    write_file(&project, "api/api.go", "package api\nimport \"db\"");
+    write_file(&project, "db/db.go", "package db\nimport \"api\"");
+
+    let output = deploy()
+        .args(["analyze", "--arch"])
+        .arg(&rules)
+        .arg(&project)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Warning: db imports api"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(!stderr.contains("api imports db"), "stderr: {}", stderr);
+
+    let output = deploy()
+        .args(["analyze", "--fail", "--arch"])
+        .arg(&rules)
+        .arg(&project)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_json_report_records_meta() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(root.path(), "app/app.go", "package app\nimport \"fmt\"");
+
+    let output = deploy()
+        .args(["analyze", "--format", "json", "--exclude-stdlib"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+
+    // Nothing but the report goes to stdout
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let meta = &report["meta"];
+    assert_eq!(meta["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(meta["options"]["exclude_stdlib"], true);
+    assert_eq!(meta["options"]["sort_by"], "instability");
+    assert_eq!(meta["paths"][0], root.path().to_str().unwrap());
+    assert_eq!(report["summary"]["modularity"], 0.0);
+    assert_eq!(report["packages"][0]["name"], "app");
+}
+
+#[test]
+fn test_summary_reports_modularity() {
+    // Two unrelated pairs split cleanly into two communities: Q = 0.5
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(root.path(), "a/a.go", "package a\nimport \"b\"");
+    write_file(root.path(), "b/b.go", "package b");
+    write_file(root.path(), "c/c.go", "package c\nimport \"d\"");
+    write_file(root.path(), "d/d.go", "package d");
+
+    let output = deploy().arg("analyze").arg(root.path()).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Unresolved imports: 0 in 0 package(s)\nModularity: 0.50\n"));
+
+    let output = deploy()
+        .args(["analyze", "--format", "json"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["summary"]["modularity"], 0.5);
+}
+
+#[test]
+fn test_validate_plan_detects_new_import() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    let project = root.path().join("project");
+    write_file(&project, "app/app.go", "package app\nimport \"lib\"");
+    write_file(&project, "lib/lib.go", "package lib");
+    write_file(&project, "util/util.go", "package util");
+
+    let output = deploy()
+        .args(["generate", "--format", "gha-matrix"])
+        .arg(&project)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let plan = root.path().join("plan.json");
+    std::fs::write(&plan, &output.stdout).unwrap();
+
+    let validate = || {
+        deploy()
+            .arg("validate-plan")
+            .arg(&project)
+            .arg(&plan)
+            .output()
+            .unwrap()
+    };
+    assert_eq!(validate().status.code(), Some(0));
+
+    // util was planned alongside lib, so lib can't start importing it
+    write_file(&project, "lib/lib.go", "package lib\nimport \"util\"");
+    let output = validate();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("lib imports util but the plan doesn't deploy util first"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_debug_parse_reports_syntax_errors() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(root.path(), "ok/ok.go", "package ok");
+    write_file(
+        root.path(),
+        "broken/broken.go",
+        "package broken\n\nimport \"ok\"\n\nfunc Run( {\n}\n",
+    );
+
+    let output = deploy().arg("analyze").arg(root.path()).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("broken.go:"), "stderr: {}", stderr);
+
+    let output = deploy()
+        .args(["analyze", "--debug-parse"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("broken.go:5:"), "stderr: {}", stderr);
+    assert!(!stderr.contains("ok.go:"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_generate_writes_packages_in_deployment_order() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    let project = root.path().join("project");
+    write_file(&project, "a/a.go", "package a\nimport \"b\"");
+    write_file(&project, "b/b.go", "package b\nimport \"c\"");
+    write_file(&project, "c/c.go", "package c");
+
+    let output_dir = root.path().join("out");
+    let output = deploy()
+        .arg("generate")
+        .arg(&project)
+        .arg("--output")
+        .arg(&output_dir)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    for file in ["1-c.go", "2-b.go", "3-a.go"] {
+        assert!(output_dir.join(file).exists(), "missing {}", file);
+    }
+}
+
+#[test]
+fn test_scores_only_printed_when_verbose() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(root.path(), "app/app.go", "package app\nimport \"lib\"");
+    write_file(root.path(), "lib/lib.go", "package lib");
+
+    let output = deploy()
+        .args(["analyze", "--format", "json"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("app: 1.00"), "stdout: {}", stdout);
+    assert!(output.stderr.is_empty());
+
+    let output = deploy()
+        .args(["analyze", "--format", "json", "--verbose"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("app: 1.00 - 1 imports"),
+        "stderr: {}",
+        stderr
+    );
+    serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap();
+}
+
+#[test]
+fn test_exclude_skips_matching_files() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(root.path(), "app/app.go", "package app\nimport \"lib\"");
+    write_file(
+        root.path(),
+        "app/app_test.go",
+        "package apptest\nimport \"app\"",
+    );
+    write_file(root.path(), "lib/lib.go", "package lib");
+    write_file(root.path(), "vendor/dep/dep.go", "package dep");
+
+    let packages = |args: &[&str]| {
+        let output = deploy()
+            .arg("analyze")
+            .args(args)
+            .arg(root.path())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let mut names: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.strip_prefix("Package: "))
+            .map(str::to_string)
+            .collect();
+        names.sort();
+        names
+    };
+
+    assert_eq!(packages(&[]), ["app", "dep", "lib"]);
+    assert_eq!(packages(&["--exclude", "vendor"]), ["app", "lib"]);
+    // Test files stay excluded alongside other patterns
+    assert_eq!(packages(&["--exclude", "lib"]), ["app", "dep"]);
+    assert_eq!(
+        packages(&["--include-tests", "--exclude", "lib"]),
+        ["app", "apptest", "dep"]
+    );
+}
+
+#[test]
+fn test_subcommands_skip_test_files() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(root.path(), "app/app.go", "package app\nimport \"lib\"");
+    write_file(
+        root.path(),
+        "app/app_test.go",
+        "package apptest\nimport \"app\"",
+    );
+    write_file(root.path(), "lib/lib.go", "package lib");
+
+    let output = deploy().arg("index").arg(root.path()).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let index: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(index.as_object().unwrap().len(), 2);
+    assert!(index.get("apptest").is_none());
+
+    let output = deploy()
+        .arg("list-packages")
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("apptest"));
+
+    // Without the test file, app has no dependents and can go
+    let output = deploy()
+        .args(["simulate", "remove"])
+        .arg(root.path())
+        .arg("app")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "app can be removed safely\n"
+    );
+}
+
+#[test]
+fn test_analyze_reads_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = deploy()
+        .args(["analyze", "--format", "json", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"package app\nimport \"lib\"\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let packages = report["packages"].as_array().unwrap();
+    assert_eq!(packages.len(), 1);
+    assert_eq!(packages[0]["name"], "app");
+}
+
+#[test]
+fn test_report_fanin_ranks_hub_first() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    for spoke in ["a", "b"] {
+        write_file(
+            root.path(),
+            &format!("{}/{}.go", spoke, spoke),
+            &format!("package {}\nimport \"hub\"", spoke),
+        );
+    }
+    write_file(root.path(), "hub/hub.go", "package hub");
+
+    let output = deploy()
+        .args(["analyze", "--report", "fanin"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let first = stdout.lines().next().unwrap();
+    assert_eq!(first.split_whitespace().collect::<Vec<_>>(), ["hub", "2"]);
+}
+
+#[test]
+fn test_order_respects_imports() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(
+        root.path(),
+        "app/app.go",
+        "package app\nimport (\n\"lib\"\n\"util\"\n)",
+    );
+    write_file(root.path(), "lib/lib.go", "package lib\nimport \"util\"");
+    write_file(root.path(), "util/util.go", "package util");
+    write_file(root.path(), "tool/tool.go", "package tool\nimport \"lib\"");
+
+    let output = deploy()
+        .args(["analyze", "--order", "--format", "json"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let order: Vec<String> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(order.len(), 4);
+    let position = |name: &str| order.iter().position(|p| p == name).unwrap();
+    for (importer, imported) in [
+        ("app", "lib"),
+        ("app", "util"),
+        ("lib", "util"),
+        ("tool", "lib"),
+    ] {
+        assert!(position(imported) < position(importer), "{:?}", order);
+    }
+
+    let output = deploy()
+        .args(["analyze", "--order"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    let text: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    assert_eq!(text, order);
+}
+
+#[test]
+fn test_top_limits_reported_packages() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(root.path(), "app/app.go", "package app\nimport \"svc\"");
+    write_file(root.path(), "svc/svc.go", "package svc\nimport \"lib\"");
+    write_file(root.path(), "lib/lib.go", "package lib");
+
+    let output = deploy()
+        .args(["analyze", "--top", "2"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let packages: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("Package: "))
+        .collect();
+    assert_eq!(packages, ["app", "svc"]);
+
+    let output = deploy()
+        .args(["analyze", "--format", "json", "--top", "1"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let packages = report["packages"].as_array().unwrap();
+    assert_eq!(packages.len(), 1);
+    assert_eq!(packages[0]["name"], "app");
+    assert_eq!(report["meta"]["options"]["top"], 1);
+}
+
+#[test]
+fn test_zone_filters_before_top() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(root.path(), "app/app.go", "package app\nimport \"svc\"");
+    write_file(root.path(), "cli/cli.go", "package cli\nimport \"svc\"");
+    write_file(root.path(), "tool/tool.go", "package tool\nimport \"lib\"");
+    write_file(root.path(), "svc/svc.go", "package svc\nimport \"lib\"");
+    write_file(root.path(), "lib/lib.go", "package lib");
+
+    let output = deploy()
+        .args([
+            "analyze", "--format", "json", "--zone", "unstable", "--top", "2",
+        ])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let names: Vec<&str> = report["packages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|package| package["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["app", "cli"]);
+    assert_eq!(report["meta"]["options"]["zone"], "unstable");
+
+    let output = deploy()
+        .args(["analyze", "--zone", "shaky"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    // Graph formats can't be filtered, which is reported instead of ignored
+    let output = deploy()
+        .args([
+            "analyze", "--format", "mermaid", "--top", "1", "--zone", "unstable",
+        ])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("can't apply zone, limit"), "{}", stderr);
+}
+
+#[test]
+fn test_legend_only_with_flag() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(root.path(), "app/app.go", "package app\nimport \"lib\"");
+    write_file(root.path(), "lib/lib.go", "package lib");
+
+    for format in ["dot", "mermaid"] {
+        let output = deploy()
+            .args(["analyze", "--format", format])
+            .arg(root.path())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        assert!(!String::from_utf8_lossy(&output.stdout).contains("Legend"));
+
+        let output = deploy()
+            .args(["analyze", "--legend", "--format", format])
+            .arg(root.path())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        assert!(String::from_utf8_lossy(&output.stdout).contains("Legend"));
+    }
+}
+
+#[test]
+fn test_analyze_merges_project_roots() {
+    let first = tempfile::tempdir().expect("Failed to create temp dir");
+    let second = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(first.path(), "app/app.go", "package app\nimport \"lib\"");
+    write_file(second.path(), "lib/lib.go", "package lib");
+
+    let output = deploy()
+        .args(["analyze", "--format", "json"])
+        .arg(first.path())
+        .arg(second.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["meta"]["paths"].as_array().unwrap().len(), 2);
+    let packages = report["packages"].as_array().unwrap();
+    assert_eq!(packages.len(), 2);
+    // The import crosses roots, so lib is stable
+    let lib = packages.iter().find(|p| p["name"] == "lib").unwrap();
+    assert_eq!(lib["metrics"]["afferent_coupling"], 1);
+}
+
+#[test]
+fn test_schema_validates_analyze_output() {
+    let root = tempfile::tempdir().expect("Failed to create temp dir");
+    write_file(root.path(), "app/app.go", "package app\nimport \"lib\"");
+    write_file(root.path(), "lib/lib.go", "package lib");
+
+    let output = deploy().arg("schema").output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let output = deploy()
+        .args(["analyze", "--format", "json", "--detailed"])
+        .arg(root.path())
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(jsonschema::is_valid(&schema, &report));
+}